@@ -0,0 +1,34 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderValue};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs `timestamp + method + path + body` with the base64 API secret (CLOB L2 auth).
+pub fn l2_signature(secret: &str, timestamp: &str, method: &str, path: &str, body: &str) -> Result<String> {
+    let key = URL_SAFE.decode(secret).context("POLY_API_SECRET is not valid base64")?;
+    let mut mac = HmacSha256::new_from_slice(&key).context("Invalid HMAC key")?;
+    mac.update(timestamp.as_bytes());
+    mac.update(method.as_bytes());
+    mac.update(path.as_bytes());
+    mac.update(body.as_bytes());
+    Ok(URL_SAFE.encode(mac.finalize().into_bytes()))
+}
+
+/// Builds the L2 header set for an authenticated CLOB request signed at `timestamp` (unix secs).
+pub fn l2_headers(config: &Config, address: &str, timestamp: i64, method: &str, path: &str, body: &str) -> Result<HeaderMap> {
+    let timestamp = timestamp.to_string();
+    let signature = l2_signature(&config.api_secret, &timestamp, method, path, body)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("POLY_ADDRESS", HeaderValue::from_str(address)?);
+    headers.insert("POLY_SIGNATURE", HeaderValue::from_str(&signature)?);
+    headers.insert("POLY_TIMESTAMP", HeaderValue::from_str(&timestamp)?);
+    headers.insert("POLY_API_KEY", HeaderValue::from_str(&config.api_key)?);
+    headers.insert("POLY_PASSPHRASE", HeaderValue::from_str(&config.api_passphrase)?);
+    Ok(headers)
+}
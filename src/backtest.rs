@@ -0,0 +1,147 @@
+use crate::config::Config;
+use crate::execution::ExecutionEngine;
+use crate::market::MarketMonitor;
+use crate::risk::{ExposureLimits, RiskManager};
+use crate::strategy::StrategyEngine;
+use crate::types::{TradeEvent, TradeStatus};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Virtual time allowed after the last frame for in-flight trades to settle.
+const DRAIN_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PnlPoint {
+    pub timestamp: DateTime<Utc>,
+    pub pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub initial_balance: Decimal,
+    pub attempts: usize,
+    pub filled: usize,
+    pub partial: usize, // Ended in emergency recovery
+    pub hit_rate: Decimal, // filled / attempts
+    pub avg_detected_edge: Decimal, // Per share, at detection
+    pub realized_edge: Decimal, // PnL per hedged share
+    pub capital_committed: Decimal, // Sum over attempts at limit prices
+    pub peak_trade_capital: Decimal,
+    pub pnl: Decimal,
+    pub max_drawdown: Decimal,
+    pub pnl_curve: Vec<PnlPoint>,
+    pub trades: Vec<TradeEvent>,
+}
+
+impl BacktestReport {
+    fn from_trades(initial_balance: Decimal, trades: Vec<TradeEvent>) -> Self {
+        let attempts = trades.len();
+        let filled = trades.iter().filter(|t| t.status == TradeStatus::Filled).count();
+        let partial = trades.iter().filter(|t| t.status == TradeStatus::PartialFillEmergency).count();
+        let ratio = |n: Decimal, d: Decimal| if d.is_zero() { Decimal::ZERO } else { n / d };
+
+        let hedged: Decimal = trades.iter().map(|t| t.filled_size).sum();
+        let pnl: Decimal = trades.iter().map(|t| t.pnl).sum();
+
+        let mut cumulative = Decimal::ZERO;
+        let mut peak = Decimal::ZERO;
+        let mut max_drawdown = Decimal::ZERO;
+        let mut pnl_curve = Vec::with_capacity(attempts);
+        for trade in &trades {
+            cumulative += trade.pnl;
+            peak = peak.max(cumulative);
+            max_drawdown = max_drawdown.max(peak - cumulative);
+            pnl_curve.push(PnlPoint { timestamp: trade.timestamp, pnl: cumulative });
+        }
+
+        Self {
+            initial_balance,
+            attempts,
+            filled,
+            partial,
+            hit_rate: ratio(Decimal::from(filled), Decimal::from(attempts)),
+            avg_detected_edge: ratio(trades.iter().map(|t| t.edge).sum(), Decimal::from(attempts)),
+            realized_edge: ratio(pnl, hedged),
+            capital_committed: trades.iter().map(|t| t.cost).sum(),
+            peak_trade_capital: trades.iter().map(|t| t.cost).max().unwrap_or_default(),
+            pnl,
+            max_drawdown,
+            pnl_curve,
+            trades,
+        }
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Attempts:          {}", self.attempts)?;
+        writeln!(f, "Filled:            {} (hit rate {:.2}%)", self.filled, self.hit_rate * Decimal::from(100))?;
+        writeln!(f, "Partial fills:     {}", self.partial)?;
+        writeln!(f, "Detected edge:     {:.4} per share", self.avg_detected_edge)?;
+        writeln!(f, "Realized edge:     {:.4} per share", self.realized_edge)?;
+        writeln!(f, "Capital committed: {:.2} (peak trade {:.2}, {:.2}% of balance)",
+            self.capital_committed, self.peak_trade_capital,
+            if self.initial_balance.is_zero() { Decimal::ZERO } else { self.peak_trade_capital / self.initial_balance * Decimal::from(100) })?;
+        writeln!(f, "PnL:               {:.4}", self.pnl)?;
+        write!(f, "Max drawdown:      {:.4}", self.max_drawdown)
+    }
+}
+
+/// Replays a capture through the real `StrategyEngine` and `ExecutionEngine`,
+/// with orders filled by the paper matcher.
+///
+/// Runs on its own single-threaded runtime with a paused clock, so frame gaps,
+/// `PAPER_LATENCY_MS` and the trade cooldown all elapse in capture time while the
+/// run itself completes as fast as the CPU allows. Books keep updating while an
+/// order is "in flight", so latency costs fills as it would live.
+pub fn run(mut config: Config, capture_dir: &Path, initial_balance: Decimal) -> Result<BacktestReport> {
+    config.paper_trading = true;
+    config.capture_dir = None;
+    config.replay_dir = Some(capture_dir.display().to_string());
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()?;
+
+    runtime.block_on(async move {
+        let market_monitor = Arc::new(MarketMonitor::for_replay(config.clone()));
+        let risk_manager = RiskManager::new(initial_balance, config.max_daily_loss_pct, config.max_trade_capital_pct)
+            .with_clock(market_monitor.clock())
+            .with_day_offset(config.risk_day_offset)
+            .with_exposure_limits(ExposureLimits::from_config(&config))
+            .with_auto_recover(config.safe_mode_auto_recover.clone());
+        let execution_engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager, market_monitor.clone()));
+        let strategy_engine = StrategyEngine::new(market_monitor.clone(), execution_engine.clone(), config);
+
+        let trades = Arc::new(Mutex::new(Vec::new()));
+        let mut trade_rx = execution_engine.trade_tx.subscribe();
+        let collected = trades.clone();
+        tokio::spawn(async move {
+            while let Ok(trade) = trade_rx.recv().await {
+                collected.lock().unwrap().push(trade);
+            }
+        });
+
+        let replay = async {
+            let result = market_monitor.replay(capture_dir, 1.0).await;
+            tokio::time::sleep(Duration::from_secs(DRAIN_SECS)).await;
+            result
+        };
+        let result = tokio::select! {
+            biased; // The strategy must subscribe to updates before the replay emits any
+            _ = strategy_engine.run() => Ok(()),
+            result = replay => result,
+        };
+        result?;
+
+        let trades = std::mem::take(&mut *trades.lock().unwrap());
+        Ok(BacktestReport::from_trades(initial_balance, trades))
+    })
+}
@@ -0,0 +1,42 @@
+use polymarket_arb_bot::backtest;
+use polymarket_arb_bot::config::Config;
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing_subscriber::FmtSubscriber;
+
+const USAGE: &str = "Usage: backtest <capture_dir> [--balance USDC] [--out report.json] [--verbose]";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
+
+    let Some(capture_dir) = args.first().filter(|a| !a.starts_with("--")).map(PathBuf::from) else {
+        anyhow::bail!(USAGE);
+    };
+
+    // Trades are reported at the end; per-order logs only on request
+    let level = if args.iter().any(|a| a == "--verbose") { tracing::Level::INFO } else { tracing::Level::WARN };
+    let subscriber = FmtSubscriber::builder().with_max_level(level).finish();
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("setting default subscriber failed");
+
+    // Strategy parameters come from the environment / .env, exactly as live
+    let config = Config::for_simulation()?;
+    let initial_balance = match flag("--balance") {
+        Some(b) => Decimal::from_str(b).map_err(|e| anyhow::anyhow!("Invalid --balance {}: {}", b, e))?,
+        None => config.paper_balance,
+    };
+    println!("Backtesting {} (min_edge {}, normalization {} x {}, cooldown {}ms, latency {}ms, queue share {})",
+        capture_dir.display(), config.min_edge, config.normalization_threshold, config.normalization_updates,
+        config.trade_cooldown_ms, config.paper_latency_ms, config.paper_queue_share);
+
+    let report = backtest::run(config, &capture_dir, initial_balance)?;
+    println!("{}", report);
+
+    if let Some(out) = flag("--out") {
+        std::fs::write(out, serde_json::to_string_pretty(&report)?)?;
+        println!("Report written to {}", out);
+    }
+    Ok(())
+}
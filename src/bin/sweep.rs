@@ -0,0 +1,70 @@
+use polymarket_arb_bot::config::Config;
+use polymarket_arb_bot::sweep::{self, SweepParam, SweepSpace};
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing_subscriber::FmtSubscriber;
+
+const USAGE: &str = "Usage: sweep <capture_dir> [--min-edge VALUES] [--min-liquidity-multiplier VALUES] \
+[--normalization-threshold VALUES] [--normalization-updates VALUES] [--trade-cooldown-ms VALUES] \
+[--random N] [--seed N] [--jobs N] [--balance USDC] [--out results.csv|results.json]
+
+VALUES is a list (0.02,0.03,0.05) or an inclusive range start:end:step (0.02:0.10:0.01).
+Parameters not swept keep their value from the environment.";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
+
+    let Some(capture_dir) = args.first().filter(|a| !a.starts_with("--")).map(PathBuf::from) else {
+        anyhow::bail!(USAGE);
+    };
+
+    let mut space = SweepSpace::new();
+    for param in SweepParam::ALL {
+        if let Some(spec) = flag(&format!("--{}", param.name().replace('_', "-"))) {
+            space = space.axis(param, sweep::parse_values(spec)?);
+        }
+    }
+    if space.axes.is_empty() {
+        anyhow::bail!("Nothing to sweep.\n{}", USAGE);
+    }
+
+    let parse_usize = |name: &str| flag(name).map(|v| v.parse::<usize>()
+        .map_err(|e| anyhow::anyhow!("Invalid {} {}: {}", name, v, e))).transpose();
+    let jobs = parse_usize("--jobs")?
+        .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+    let seed = parse_usize("--seed")?.unwrap_or(0) as u64;
+
+    let subscriber = FmtSubscriber::builder().with_max_level(tracing::Level::WARN).finish();
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("setting default subscriber failed");
+
+    let points = match parse_usize("--random")? {
+        Some(n) => space.sample(n, seed),
+        None => space.grid(),
+    };
+    println!("Sweeping {} parameter sets over {} on {} threads", points.len(), capture_dir.display(), jobs);
+
+    let config = Config::for_simulation()?;
+    let initial_balance = match flag("--balance") {
+        Some(b) => Decimal::from_str(b).map_err(|e| anyhow::anyhow!("Invalid --balance {}: {}", b, e))?,
+        None => config.paper_balance,
+    };
+    let results = sweep::run(&config, &capture_dir, initial_balance, &space, points, jobs);
+
+    let csv = sweep::to_csv(&results);
+    match flag("--out") {
+        Some(out) if out.ends_with(".json") => std::fs::write(out, serde_json::to_string_pretty(&results)?)?,
+        Some(out) => std::fs::write(out, &csv)?,
+        None => {}
+    }
+    // Top of the table on stdout
+    for line in csv.lines().take(11) {
+        println!("{}", line);
+    }
+    if let Some(out) = flag("--out") {
+        println!("{} results written to {}", results.len(), out);
+    }
+    Ok(())
+}
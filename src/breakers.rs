@@ -0,0 +1,87 @@
+use crate::config::Config;
+use crate::risk::RiskManager;
+use crate::safe_mode::{SafeModeReason, SafeModeScope};
+use crate::telemetry::Telemetry;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Thresholds for the non-PnL circuit breakers.
+#[derive(Debug, Clone)]
+pub struct BreakerLimits {
+    pub ws_disconnect_secs: i64,
+    pub order_window_secs: i64, // Error rate and latency look back this far
+    pub min_order_samples: usize, // Below this, rates and percentiles are noise
+    pub max_order_error_rate: Decimal,
+    pub max_order_latency_p99_ms: u64,
+    pub max_partial_fills_per_hour: usize,
+}
+
+impl BreakerLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            ws_disconnect_secs: config.ws_disconnect_secs,
+            order_window_secs: config.breaker_window_secs,
+            min_order_samples: config.breaker_min_samples,
+            max_order_error_rate: config.max_order_error_rate,
+            max_order_latency_p99_ms: config.max_order_latency_p99_ms,
+            max_partial_fills_per_hour: config.max_partial_fills_per_hour,
+        }
+    }
+}
+
+/// Halts new entries on connectivity and execution-quality problems, and lets
+/// those halts clear (per `SAFE_MODE_AUTO_RECOVER`) once the numbers recover.
+pub struct CircuitBreakers {
+    telemetry: Arc<Telemetry>,
+    risk_manager: RiskManager,
+    limits: BreakerLimits,
+}
+
+impl CircuitBreakers {
+    pub fn new(telemetry: Arc<Telemetry>, risk_manager: RiskManager, limits: BreakerLimits) -> Self {
+        Self { telemetry, risk_manager, limits }
+    }
+
+    /// Evaluates every breaker once.
+    pub fn check(&self) {
+        let limits = &self.limits;
+
+        let ws_down = self.telemetry.ws_down_for()
+            .filter(|d| d.num_seconds() >= limits.ws_disconnect_secs)
+            .map(|d| format!("Market WS down for {}s", d.num_seconds()));
+        self.apply(SafeModeReason::StaleData, ws_down);
+
+        let stats = self.telemetry.order_stats(chrono::Duration::seconds(limits.order_window_secs));
+        let enough = stats.count >= limits.min_order_samples.max(1);
+        let error_rate = Decimal::from(stats.errors) / Decimal::from(stats.count.max(1));
+        let errors = (enough && error_rate > limits.max_order_error_rate)
+            .then(|| format!("{} of {} order requests failed in {}s", stats.errors, stats.count, limits.order_window_secs));
+        self.apply(SafeModeReason::OrderErrors, errors);
+
+        let slow = stats.p99_latency_ms
+            .filter(|p99| enough && *p99 > limits.max_order_latency_p99_ms)
+            .map(|p99| format!("Order latency p99 {}ms over {} requests", p99, stats.count));
+        self.apply(SafeModeReason::OrderLatency, slow);
+
+        let partial_fills = self.telemetry.partial_fills_within(chrono::Duration::hours(1));
+        let too_many = (partial_fills > limits.max_partial_fills_per_hour)
+            .then(|| format!("{} partial-fill emergencies in the last hour", partial_fills));
+        self.apply(SafeModeReason::PartialFillRate, too_many);
+    }
+
+    fn apply(&self, reason: SafeModeReason, tripped: Option<String>) {
+        match tripped {
+            Some(detail) => self.risk_manager.enter_safe_mode(reason, SafeModeScope::Global, &detail),
+            None => self.risk_manager.resolve(reason, &SafeModeScope::Global),
+        }
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            self.check();
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const FLUSH_INTERVAL_MS: i64 = 1000;
+
+/// One raw market WS frame as received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedFrame {
+    pub ts: i64, // Receive time, unix millis
+    pub frame: String,
+}
+
+/// Writes raw WS frames to gzip-compressed JSON-lines files, starting a new
+/// file every `rotate_secs`.
+///
+/// Files are named `ws-<UTC timestamp>.jsonl.gz`, so sorting by name gives
+/// capture order. The discovery response goes to `markets-<UTC timestamp>.json.gz`.
+pub struct CaptureWriter {
+    dir: PathBuf,
+    rotate_secs: i64,
+    current: Option<GzEncoder<BufWriter<File>>>,
+    opened_at: DateTime<Utc>,
+    last_flush: DateTime<Utc>,
+}
+
+impl CaptureWriter {
+    pub fn new(dir: impl Into<PathBuf>, rotate_secs: i64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create capture dir {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            rotate_secs,
+            current: None,
+            opened_at: Utc::now(),
+            last_flush: Utc::now(),
+        })
+    }
+
+    pub fn write_frame(&mut self, received_at: DateTime<Utc>, frame: &str) -> Result<()> {
+        let rotate = self.current.is_none()
+            || received_at.signed_duration_since(self.opened_at).num_seconds() >= self.rotate_secs;
+        if rotate {
+            self.finish()?;
+            let path = self.dir.join(format!("ws-{}.jsonl.gz", received_at.format("%Y%m%dT%H%M%S%.3fZ")));
+            info!("Capturing WS frames to {}", path.display());
+            let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+            self.current = Some(GzEncoder::new(BufWriter::new(file), Compression::default()));
+            self.opened_at = received_at;
+        }
+
+        let encoder = self.current.as_mut().expect("capture file is open");
+        let line = serde_json::to_string(&CapturedFrame { ts: received_at.timestamp_millis(), frame: frame.to_string() })?;
+        encoder.write_all(line.as_bytes())?;
+        encoder.write_all(b"\n")?;
+
+        // Sync-flush regularly so a crash loses at most the last second
+        if received_at.signed_duration_since(self.last_flush).num_milliseconds() >= FLUSH_INTERVAL_MS {
+            encoder.flush()?;
+            self.last_flush = received_at;
+        }
+        Ok(())
+    }
+
+    pub fn write_markets(&self, fetched_at: DateTime<Utc>, body: &str) -> Result<()> {
+        let path = self.dir.join(format!("markets-{}.json.gz", fetched_at.format("%Y%m%dT%H%M%S%.3fZ")));
+        let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        encoder.write_all(body.as_bytes())?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Completes the current file, if any.
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(encoder) = self.current.take() {
+            encoder.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CaptureWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!("Failed to close capture file: {}", e);
+        }
+    }
+}
+
+fn capture_files(dir: &Path, prefix: &str) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read capture dir {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name().and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(prefix) && n.ends_with(".gz"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Discovery responses in a capture dir, oldest first.
+pub fn read_markets(dir: &Path) -> Result<Vec<String>> {
+    capture_files(dir, "markets-")?
+        .iter()
+        .map(|path| {
+            let mut body = String::new();
+            MultiGzDecoder::new(File::open(path)?).read_to_string(&mut body)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            Ok(body)
+        })
+        .collect()
+}
+
+/// Every captured WS frame in a capture dir, in receive order.
+///
+/// Files are decompressed one at a time as the iterator advances. A file cut
+/// short by a crash is read up to its last complete line.
+pub fn read_frames(dir: &Path) -> Result<FrameReader> {
+    Ok(FrameReader {
+        files: capture_files(dir, "ws-")?.into(),
+        current: None,
+    })
+}
+
+pub struct FrameReader {
+    files: VecDeque<PathBuf>,
+    current: Option<(PathBuf, Lines<BufReader<MultiGzDecoder<File>>>)>,
+}
+
+impl Iterator for FrameReader {
+    type Item = CapturedFrame;
+
+    fn next(&mut self) -> Option<CapturedFrame> {
+        loop {
+            let Some((path, lines)) = self.current.as_mut() else {
+                let path = self.files.pop_front()?;
+                match File::open(&path) {
+                    Ok(file) => self.current = Some((path, BufReader::new(MultiGzDecoder::new(file)).lines())),
+                    Err(e) => warn!("Skipping capture {}: {}", path.display(), e),
+                }
+                continue;
+            };
+
+            match lines.next() {
+                Some(Ok(line)) => match serde_json::from_str::<CapturedFrame>(&line) {
+                    Ok(frame) => return Some(frame),
+                    Err(e) => warn!("Skipping bad capture line in {}: {}", path.display(), e),
+                },
+                Some(Err(e)) => {
+                    warn!("Capture {} truncated: {}", path.display(), e);
+                    self.current = None;
+                }
+                None => self.current = None,
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use std::sync::RwLock;
+
+/// Source of "now" for cooldowns, expirations, order timestamps and auth.
+///
+/// Live runs use `SystemClock`; replays and backtests run on capture time via
+/// `ReplayClock`; tests drive time by hand with `ManualClock`.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: RwLock<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self { now: RwLock::new(start) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.write().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.write().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.read().unwrap()
+    }
+}
+
+/// Maps the runtime clock onto capture time while replaying.
+///
+/// At speed > 0, time flows from the first frame's receive time at `speed`x the
+/// tokio clock (so a paused runtime replays in virtual time). At speed 0 it
+/// jumps from frame to frame. Before the replay starts it reads the wall clock.
+#[derive(Debug, Default)]
+pub struct ReplayClock {
+    state: RwLock<Option<ReplayState>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ReplayState {
+    origin: DateTime<Utc>, // Receive time of the first frame
+    started: tokio::time::Instant,
+    speed: f64,
+    last_frame: DateTime<Utc>,
+}
+
+impl ReplayClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&self, origin: DateTime<Utc>, speed: f64) {
+        *self.state.write().unwrap() = Some(ReplayState {
+            origin,
+            started: tokio::time::Instant::now(),
+            speed,
+            last_frame: origin,
+        });
+    }
+
+    pub fn on_frame(&self, received_at: DateTime<Utc>) {
+        if let Some(state) = self.state.write().unwrap().as_mut() {
+            state.last_frame = received_at;
+        }
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now(&self) -> DateTime<Utc> {
+        match *self.state.read().unwrap() {
+            None => Utc::now(),
+            Some(state) if state.speed > 0.0 => {
+                let elapsed = state.started.elapsed().as_secs_f64() * state.speed;
+                state.origin + chrono::Duration::microseconds((elapsed * 1e6) as i64)
+            }
+            Some(state) => state.last_frame,
+        }
+    }
+}
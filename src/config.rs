@@ -1,8 +1,15 @@
 use std::env;
 use std::str::FromStr;
 use rust_decimal::Decimal;
+use chrono::FixedOffset;
+use crate::safe_mode::SafeModeReason;
+use crate::telemetry;
+use crate::types::{MarkPrice, OrderType, MIN_GTD_LEAD_SECS};
 use dotenv::dotenv;
 use anyhow::{Context, Result};
+use ethers::core::rand::thread_rng;
+use ethers::signers::{LocalWallet, Signer};
+use tracing::warn;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -13,20 +20,87 @@ pub struct Config {
     pub funder_address: String,
     pub http_url: String,
     pub ws_url: String,
+    pub user_ws_url: String,
+    pub rpc_url: Option<String>,
     pub max_daily_loss_pct: Decimal,
     pub max_trade_capital_pct: Decimal,
     pub min_edge: Decimal,
+    // Exposure Limits (fractions of the balance, by cost of shares held)
+    pub max_market_capital_pct: Decimal,
+    pub max_tag_capital_pct: Decimal, // Across all markets sharing a tag
+    pub max_total_capital_pct: Decimal,
+    pub max_open_positions: usize, // Markets we hold shares in
+    pub risk_day_offset: FixedOffset, // Daily PnL rolls over at midnight here
+    pub risk_state_file: String, // Persisted risk state (live mode)
+    // Safe Mode
+    pub safe_mode_auto_recover: Vec<SafeModeReason>, // Halts that clear once their condition does
+    pub audit_log_file: String, // Safe-mode transitions, one JSON line each (live mode)
+    pub control_addr: Option<String>, // Operator commands (status, halt, resume, kill)
+    pub kill_switch_file: Option<String>, // Trading is killed when this file appears
+    pub kill_switch_flatten: bool, // Also sell off every position when killed
+    // Circuit Breakers
+    pub ws_disconnect_secs: i64, // Market feed down this long halts entries
+    pub breaker_window_secs: i64, // Order error rate and latency window
+    pub breaker_min_samples: usize,
+    pub max_order_error_rate: Decimal,
+    pub max_order_latency_p99_ms: u64,
+    pub max_partial_fills_per_hour: usize,
+    // Balance Sync
+    pub balance_sync_secs: u64, // Re-read collateral this often
+    pub balance_drift_tolerance: Decimal, // USDC of unexplained change before alerting
+    pub paper_balance: Decimal, // Starting balance when not trading live
+    // Mark-to-Market
+    pub mark_interval_secs: u64, // Revalue positions this often
+    pub mark_price: MarkPrice,
+    #[allow(dead_code)]
     pub poll_interval_ms: u64,
     // Safety & Re-entry
     pub min_liquidity_multiplier: Decimal, // 5.0
     pub normalization_threshold: Decimal, // 0.99
     pub normalization_updates: u32, // 3
     pub trade_cooldown_ms: i64, // 30000
+    // Order Types
+    pub arb_order_type: OrderType, // GTD expirations are stamped per order from gtd_ttl_secs
+    pub gtd_ttl_secs: i64, // Lifetime of GTD legs
+    pub batch_orders: bool, // Submit arb legs via POST /orders
+    // Emergency Recovery
+    pub emergency_max_loss: Decimal, // USDC we accept losing to complete a one-sided fill
+    pub emergency_min_sell_price: Decimal, // Never dump exposure below this price
+    pub emergency_max_steps: u32, // Bid ladder steps before giving up
+    // Paper Trading
+    pub paper_trading: bool, // Simulate fills instead of sending orders
+    pub paper_latency_ms: u64,
+    pub paper_slippage_bps: Decimal,
+    pub paper_queue_share: Decimal, // Share of displayed depth we can take before other takers
+    // Record & Replay
+    pub capture_dir: Option<String>, // Write raw market WS frames here
+    pub capture_rotate_secs: i64, // Start a new capture file this often
+    pub replay_dir: Option<String>, // Feed a capture back instead of connecting
+    pub replay_speed: f64, // 1.0 = original pace, 0 = as fast as possible
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
         dotenv().ok();
+        Self::load(true)
+    }
+
+    /// Config for offline runs (backtests) that never talk to the exchange.
+    /// Missing credentials are replaced by placeholders and a throwaway key.
+    pub fn for_simulation() -> Result<Self> {
+        dotenv().ok();
+        Self::load(false)
+    }
+
+    fn load(require_credentials: bool) -> Result<Self> {
+        let throwaway = LocalWallet::new(&mut thread_rng());
+        let credential = |name: &str, placeholder: String| -> Result<String> {
+            match env::var(name) {
+                Ok(value) => Ok(value),
+                Err(_) if !require_credentials => Ok(placeholder),
+                Err(_) => anyhow::bail!("{} must be set", name),
+            }
+        };
 
         let max_daily_loss_pct = Decimal::from_str(
             &env::var("MAX_DAILY_LOSS_PCT").unwrap_or_else(|_| "0.02".to_string())
@@ -40,22 +114,181 @@ impl Config {
             &env::var("MIN_EDGE").unwrap_or_else(|_| "0.05".to_string())
         ).context("Invalid MIN_EDGE")?;
 
+        let max_market_capital_pct = Decimal::from_str(
+            &env::var("MAX_MARKET_CAPITAL_PCT").unwrap_or_else(|_| "0.03".to_string())
+        ).context("Invalid MAX_MARKET_CAPITAL_PCT")?;
+
+        let max_tag_capital_pct = Decimal::from_str(
+            &env::var("MAX_TAG_CAPITAL_PCT").unwrap_or_else(|_| "0.25".to_string())
+        ).context("Invalid MAX_TAG_CAPITAL_PCT")?;
+
+        let max_total_capital_pct = Decimal::from_str(
+            &env::var("MAX_TOTAL_CAPITAL_PCT").unwrap_or_else(|_| "0.5".to_string())
+        ).context("Invalid MAX_TOTAL_CAPITAL_PCT")?;
+
+        let max_open_positions = env::var("MAX_OPEN_POSITIONS").unwrap_or_else(|_| "20".to_string())
+            .parse::<usize>().context("Invalid MAX_OPEN_POSITIONS")?;
+
+        let risk_day_offset = env::var("RISK_DAY_UTC_OFFSET").unwrap_or_else(|_| "+00:00".to_string())
+            .parse::<FixedOffset>().context("Invalid RISK_DAY_UTC_OFFSET: expected e.g. +00:00 or -05:00")?;
+
+        let safe_mode_auto_recover = env::var("SAFE_MODE_AUTO_RECOVER").unwrap_or_else(|_| "stale_data,reconciliation_drift,order_errors,order_latency".to_string())
+            .split(',')
+            .filter(|r| !r.trim().is_empty())
+            .map(SafeModeReason::from_str)
+            .collect::<Result<Vec<_>>>()
+            .context("Invalid SAFE_MODE_AUTO_RECOVER")?;
+
+        let ws_disconnect_secs = env::var("WS_DISCONNECT_SECS").unwrap_or_else(|_| "10".to_string())
+            .parse::<i64>().context("Invalid WS_DISCONNECT_SECS")?;
+
+        let mut breaker_window_secs = env::var("BREAKER_WINDOW_SECS").unwrap_or_else(|_| "300".to_string())
+            .parse::<i64>().context("Invalid BREAKER_WINDOW_SECS")?;
+        if breaker_window_secs > telemetry::RETENTION_SECS {
+            warn!("BREAKER_WINDOW_SECS {} is longer than the {}s of order history kept; using {}",
+                breaker_window_secs, telemetry::RETENTION_SECS, telemetry::RETENTION_SECS);
+            breaker_window_secs = telemetry::RETENTION_SECS;
+        }
+
+        let breaker_min_samples = env::var("BREAKER_MIN_SAMPLES").unwrap_or_else(|_| "5".to_string())
+            .parse::<usize>().context("Invalid BREAKER_MIN_SAMPLES")?;
+
+        let max_order_error_rate = Decimal::from_str(
+            &env::var("MAX_ORDER_ERROR_RATE").unwrap_or_else(|_| "0.5".to_string())
+        ).context("Invalid MAX_ORDER_ERROR_RATE")?;
+
+        let max_order_latency_p99_ms = env::var("MAX_ORDER_LATENCY_P99_MS").unwrap_or_else(|_| "2000".to_string())
+            .parse::<u64>().context("Invalid MAX_ORDER_LATENCY_P99_MS")?;
+
+        let max_partial_fills_per_hour = env::var("MAX_PARTIAL_FILLS_PER_HOUR").unwrap_or_else(|_| "3".to_string())
+            .parse::<usize>().context("Invalid MAX_PARTIAL_FILLS_PER_HOUR")?;
+
+        let balance_sync_secs = env::var("BALANCE_SYNC_SECS").unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>().context("Invalid BALANCE_SYNC_SECS")?;
+
+        let balance_drift_tolerance = Decimal::from_str(
+            &env::var("BALANCE_DRIFT_TOLERANCE").unwrap_or_else(|_| "1.0".to_string())
+        ).context("Invalid BALANCE_DRIFT_TOLERANCE")?;
+
+        let paper_balance = Decimal::from_str(
+            &env::var("PAPER_BALANCE").unwrap_or_else(|_| "1000".to_string())
+        ).context("Invalid PAPER_BALANCE")?;
+
+        let mark_interval_secs = env::var("MARK_INTERVAL_SECS").unwrap_or_else(|_| "5".to_string())
+            .parse::<u64>().context("Invalid MARK_INTERVAL_SECS")?;
+
+        let mark_price = env::var("MARK_PRICE").unwrap_or_else(|_| "BID".to_string())
+            .parse::<MarkPrice>().context("Invalid MARK_PRICE")?;
+
+        let min_liquidity_multiplier = Decimal::from_str(
+            &env::var("MIN_LIQUIDITY_MULTIPLIER").unwrap_or_else(|_| "5.0".to_string())
+        ).context("Invalid MIN_LIQUIDITY_MULTIPLIER")?;
+
+        let normalization_threshold = Decimal::from_str(
+            &env::var("NORMALIZATION_THRESHOLD").unwrap_or_else(|_| "0.99".to_string())
+        ).context("Invalid NORMALIZATION_THRESHOLD")?;
+
+        let normalization_updates = env::var("NORMALIZATION_UPDATES").unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>().context("Invalid NORMALIZATION_UPDATES")?;
+
+        let trade_cooldown_ms = env::var("TRADE_COOLDOWN_MS").unwrap_or_else(|_| "30000".to_string()) // 30 seconds
+            .parse::<i64>().context("Invalid TRADE_COOLDOWN_MS")?;
+
+        let arb_order_type = env::var("ARB_ORDER_TYPE").unwrap_or_else(|_| "FOK".to_string())
+            .parse::<OrderType>().context("Invalid ARB_ORDER_TYPE")?;
+
+        let gtd_ttl_secs = env::var("GTD_TTL_SECS").unwrap_or_else(|_| "120".to_string())
+            .parse::<i64>().context("Invalid GTD_TTL_SECS")?;
+        // The expiration is stamped before the order is built, so exactly the minimum would already be short
+        if gtd_ttl_secs <= MIN_GTD_LEAD_SECS {
+            anyhow::bail!("Invalid GTD_TTL_SECS {}: the CLOB rejects GTD orders expiring within {}s, use more than that", gtd_ttl_secs, MIN_GTD_LEAD_SECS);
+        }
+
+        let emergency_max_loss = Decimal::from_str(
+            &env::var("EMERGENCY_MAX_LOSS").unwrap_or_else(|_| "1.0".to_string())
+        ).context("Invalid EMERGENCY_MAX_LOSS")?;
+
+        let emergency_min_sell_price = Decimal::from_str(
+            &env::var("EMERGENCY_MIN_SELL_PRICE").unwrap_or_else(|_| "0.01".to_string())
+        ).context("Invalid EMERGENCY_MIN_SELL_PRICE")?;
+
+        let paper_slippage_bps = Decimal::from_str(
+            &env::var("PAPER_SLIPPAGE_BPS").unwrap_or_else(|_| "0".to_string())
+        ).context("Invalid PAPER_SLIPPAGE_BPS")?;
+
+        let paper_queue_share = Decimal::from_str(
+            &env::var("PAPER_QUEUE_SHARE").unwrap_or_else(|_| "1.0".to_string())
+        ).context("Invalid PAPER_QUEUE_SHARE")?;
+        if paper_queue_share <= Decimal::ZERO || paper_queue_share > Decimal::ONE {
+            anyhow::bail!("Invalid PAPER_QUEUE_SHARE {}: must be in (0, 1]", paper_queue_share);
+        }
+
+        let paper_latency_ms = env::var("PAPER_LATENCY_MS").unwrap_or_else(|_| "50".to_string())
+            .parse::<u64>().context("Invalid PAPER_LATENCY_MS")?;
+
+        let capture_rotate_secs = env::var("CAPTURE_ROTATE_SECS").unwrap_or_else(|_| "3600".to_string())
+            .parse::<i64>().context("Invalid CAPTURE_ROTATE_SECS")?;
+
+        let replay_speed = env::var("REPLAY_SPEED").unwrap_or_else(|_| "1.0".to_string())
+            .parse::<f64>().context("Invalid REPLAY_SPEED")?;
+        if replay_speed.is_nan() || replay_speed < 0.0 {
+            anyhow::bail!("Invalid REPLAY_SPEED {}: must be >= 0", replay_speed);
+        }
+
         Ok(Self {
-            api_key: env::var("POLY_API_KEY").context("POLY_API_KEY must be set")?,
-            api_secret: env::var("POLY_API_SECRET").context("POLY_API_SECRET must be set")?,
-            api_passphrase: env::var("POLY_API_PASSPHRASE").context("POLY_API_PASSPHRASE must be set")?,
-            private_key: env::var("POLY_PRIVATE_KEY").context("POLY_PRIVATE_KEY must be set")?,
-            funder_address: env::var("POLY_FUNDER").context("POLY_FUNDER must be set")?,
+            api_key: credential("POLY_API_KEY", String::new())?,
+            api_secret: credential("POLY_API_SECRET", String::new())?,
+            api_passphrase: credential("POLY_API_PASSPHRASE", String::new())?,
+            private_key: credential("POLY_PRIVATE_KEY", hex::encode(throwaway.signer().to_bytes()))?,
+            funder_address: credential("POLY_FUNDER", format!("{:?}", throwaway.address()))?,
             http_url: env::var("POLY_HTTP_URL").unwrap_or_else(|_| "https://clob.polymarket.com".to_string()),
             ws_url: env::var("POLY_WS_URL").unwrap_or_else(|_| "wss://clob.polymarket.com/ws/".to_string()),
+            user_ws_url: env::var("POLY_USER_WS_URL").unwrap_or_else(|_| "wss://ws-subscriptions-clob.polymarket.com/ws/user".to_string()),
+            rpc_url: env::var("POLY_RPC_URL").ok(),
             max_daily_loss_pct,
             max_trade_capital_pct,
             min_edge,
+            max_market_capital_pct,
+            max_tag_capital_pct,
+            max_total_capital_pct,
+            max_open_positions,
+            risk_day_offset,
+            risk_state_file: env::var("RISK_STATE_FILE").unwrap_or_else(|_| "risk_state.json".to_string()),
+            safe_mode_auto_recover,
+            audit_log_file: env::var("AUDIT_LOG_FILE").unwrap_or_else(|_| "safe_mode_audit.jsonl".to_string()),
+            control_addr: env::var("CONTROL_ADDR").ok().filter(|a| !a.is_empty()),
+            kill_switch_file: env::var("KILL_SWITCH_FILE").ok().filter(|f| !f.is_empty()),
+            kill_switch_flatten: env::var("KILL_SWITCH_FLATTEN").map(|v| v == "true" || v == "1").unwrap_or(false),
+            ws_disconnect_secs,
+            breaker_window_secs,
+            breaker_min_samples,
+            max_order_error_rate,
+            max_order_latency_p99_ms,
+            max_partial_fills_per_hour,
+            balance_sync_secs,
+            balance_drift_tolerance,
+            paper_balance,
+            mark_interval_secs,
+            mark_price,
             poll_interval_ms: 250,
-            min_liquidity_multiplier: Decimal::new(5, 0),
-            normalization_threshold: Decimal::new(99, 2), // 0.99
-            normalization_updates: 3,
-            trade_cooldown_ms: 30000, // 30 seconds
+            min_liquidity_multiplier,
+            normalization_threshold,
+            normalization_updates,
+            trade_cooldown_ms,
+            arb_order_type,
+            gtd_ttl_secs,
+            emergency_max_loss,
+            emergency_min_sell_price,
+            emergency_max_steps: 5,
+            batch_orders: env::var("BATCH_ORDERS").map(|v| v != "false" && v != "0").unwrap_or(true),
+            paper_trading: env::var("PAPER_TRADING").map(|v| v == "true" || v == "1").unwrap_or(false),
+            paper_latency_ms,
+            paper_slippage_bps,
+            paper_queue_share,
+            capture_dir: env::var("CAPTURE_DIR").ok(),
+            capture_rotate_secs,
+            replay_dir: env::var("REPLAY_DIR").ok(),
+            replay_speed,
         })
     }
 }
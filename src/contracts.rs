@@ -0,0 +1,23 @@
+use ethers::contract::abigen;
+
+/// Polymarket CTF Exchange on Polygon (same contract the EIP-712 domain points at).
+pub const CTF_EXCHANGE_ADDRESS: &str = "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E";
+
+abigen!(
+    CtfExchange,
+    r#"[
+        function nonces(address) external view returns (uint256)
+        function incrementNonce() external
+    ]"#
+);
+
+/// USDC.e on Polygon, the collateral the CLOB settles in (6 decimals).
+pub const USDC_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+
+abigen!(
+    Erc20,
+    r#"[
+        function balanceOf(address) external view returns (uint256)
+        function allowance(address,address) external view returns (uint256)
+    ]"#
+);
@@ -0,0 +1,169 @@
+use crate::kill_switch::KillSwitch;
+use crate::risk::RiskManager;
+use crate::safe_mode::{SafeModeReason, SafeModeScope};
+use rust_decimal::Decimal;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+const HELP: &str = "Commands:\n\
+    status                               active halts\n\
+    halt [<market_id>] [-- <detail>]     manual halt, global or one market\n\
+    resume <reason|all> [global|<market_id>]  clear halts\n\
+    kill [flatten]                       halt, cancel all orders (and sell off positions)\n\
+    invalidate                           void every signed order on-chain (costs gas)\n\
+    help";
+
+/// Line-based operator console (`nc 127.0.0.1 7878`).
+///
+/// Every reply ends with a line starting `OK` or `ERR`. There is no
+/// authentication: bind it to loopback only.
+pub struct ControlServer {
+    listener: TcpListener,
+    risk_manager: RiskManager,
+    kill_switch: Option<Arc<KillSwitch>>,
+}
+
+impl ControlServer {
+    pub async fn bind(addr: &str, risk_manager: RiskManager) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        info!("Control server listening on {}", listener.local_addr()?);
+        Ok(Self { listener, risk_manager, kill_switch: None })
+    }
+
+    /// Enables the `kill` command.
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub async fn run(self) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, peer)) => {
+                    let risk_manager = self.risk_manager.clone();
+                    let kill_switch = self.kill_switch.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve(stream, &risk_manager, kill_switch.as_deref()).await {
+                            warn!("Control connection {} failed: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Control accept failed: {}", e),
+            }
+        }
+    }
+}
+
+async fn serve(stream: TcpStream, risk_manager: &RiskManager, kill_switch: Option<&KillSwitch>) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        info!(target: "audit", "Control command: {}", line.trim());
+        let reply = match line.split_whitespace().next() {
+            Some("kill") => kill(kill_switch, &line).await,
+            Some("invalidate") => invalidate(kill_switch).await,
+            _ => execute(risk_manager, &line),
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Runs `kill [flatten]`, which waits for the cancels (and sales) to finish.
+pub async fn kill(kill_switch: Option<&KillSwitch>, line: &str) -> String {
+    let Some(kill_switch) = kill_switch else {
+        return "ERR kill switch not available\n".to_string();
+    };
+    let flatten = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["kill"] => false,
+        ["kill", "flatten"] => true,
+        _ => return "ERR usage: kill [flatten]\n".to_string(),
+    };
+
+    let report = kill_switch.trigger("control command", flatten).await;
+    match report.cancel_error {
+        Some(e) => format!("ERR halted, but cancel-all failed: {}\n", e),
+        None if report.unsold > Decimal::ZERO => {
+            format!("ERR halted and cancelled {}, but {} shares unsold\n", report.cancelled, report.unsold)
+        }
+        None if report.flattened => format!("OK killed: cancelled {}, flattened\n", report.cancelled),
+        None => format!("OK killed: cancelled {}\n", report.cancelled),
+    }
+}
+
+/// Runs `invalidate`: bumps the exchange nonce, voiding every order signed so far.
+pub async fn invalidate(kill_switch: Option<&KillSwitch>) -> String {
+    let Some(kill_switch) = kill_switch else {
+        return "ERR kill switch not available\n".to_string();
+    };
+    match kill_switch.invalidate_orders().await {
+        Ok(nonce) => format!("OK nonce {}\n", nonce),
+        Err(e) => format!("ERR {}\n", e),
+    }
+}
+
+/// Runs one command line and returns the reply, newline-terminated.
+pub fn execute(risk_manager: &RiskManager, line: &str) -> String {
+    let (command, detail) = match line.split_once("--") {
+        Some((command, detail)) => (command, detail.trim()),
+        None => (line, ""),
+    };
+    let args: Vec<&str> = command.split_whitespace().collect();
+    let mut reply = String::new();
+    match args[..] {
+        ["status"] => {
+            let halts = risk_manager.halts();
+            for halt in &halts {
+                writeln!(reply, "HALT {}", halt).unwrap();
+            }
+            if halts.is_empty() {
+                reply.push_str("OK trading\n");
+            } else {
+                writeln!(reply, "OK {} active", halts.len()).unwrap();
+            }
+        }
+        ["halt"] | ["halt", _] => {
+            let scope = args.get(1).map_or(SafeModeScope::Global, |m| SafeModeScope::Market(m.to_string()));
+            let detail = if detail.is_empty() { "Operator halt" } else { detail };
+            risk_manager.enter_safe_mode(SafeModeReason::Manual, scope.clone(), detail);
+            writeln!(reply, "OK halted {}", scope).unwrap();
+        }
+        ["resume", reason] | ["resume", reason, _] => {
+            let reason = match reason {
+                "all" => None,
+                r => match SafeModeReason::from_str(r) {
+                    Ok(r) => Some(r),
+                    Err(e) => return format!("ERR {}\n", e),
+                },
+            };
+            let scope = args.get(2).map(|s| match *s {
+                "global" => SafeModeScope::Global,
+                m => SafeModeScope::Market(m.to_string()),
+            });
+            let cleared = risk_manager.recover(reason, scope.as_ref());
+            for halt in &cleared {
+                writeln!(reply, "CLEARED {}", halt).unwrap();
+            }
+            if cleared.is_empty() {
+                reply.push_str("ERR no matching halt\n");
+            } else {
+                writeln!(reply, "OK cleared {}", cleared.len()).unwrap();
+            }
+        }
+        ["help"] => writeln!(reply, "{}\nOK", HELP).unwrap(),
+        _ => reply.push_str("ERR unknown command (try help)\n"),
+    }
+    reply
+}
@@ -1,96 +1,387 @@
-use crate::types::{OrderRequest, Side, TradeStatus};
+use crate::types::{ArbLeg, BalanceAllowanceResponse, CollateralBalance, CancelResponse, CancelScope, ClobTrade, FillReport, OpenOrder, OrderRequest, OrderResponse, OrderType, Level, Side, TradeEvent, MIN_GTD_LEAD_SECS, TradeStatus, WsOrderEvent, WsTradeEvent, parse_amount, usdc_from_base_units};
+use crate::auth::l2_headers;
 use crate::risk::RiskManager;
+use crate::market::MarketMonitor;
 use crate::config::Config;
-use rust_decimal::Decimal;
+use crate::contracts::{CtfExchange, Erc20, CTF_EXCHANGE_ADDRESS, USDC_ADDRESS};
+use crate::nonce::NonceManager;
+use crate::orders::{OrderRegistry, OrderState, SettlementStatus, TradeSettlement};
+use crate::paper::PaperMatcher;
+use crate::telemetry::Telemetry;
+use crate::positions::Valuation;
+use crate::safe_mode::{SafeModeReason, SafeModeScope};
+use crate::clock::Clock;
+use chrono::{DateTime, Utc};
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport};
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
 use rust_decimal::prelude::ToPrimitive;
 use std::time::{Duration, Instant};
-use tracing::{info, error, warn};
-use reqwest::Client;
+use tracing::{info, error, warn, debug};
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde_json::json;
+use futures_util::future::join_all;
+use tokio::sync::broadcast;
 use tokio::time::sleep;
-use ethers::core::types::{H256, Address, U256};
+use ethers::core::types::{Address, U256};
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Http, Provider};
 use ethers::signers::{LocalWallet, Signer};
-use ethers::contract::Eip712;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::utils::to_checksum;
 use crate::types::Order;
 use std::str::FromStr;
+use std::sync::Arc;
 
 const CHAIN_ID: u64 = 137; // Polygon Mainnet
+const FILL_QUERY_ATTEMPTS: u32 = 5;
+const FILL_QUERY_DELAY_MS: u64 = 200;
+const REBALANCE_STEP_DELAY_MS: u64 = 250;
+const MAX_PRICE: Decimal = dec!(0.99); // Highest valid tick
 
 pub struct ExecutionEngine {
-    client: Client,
+    http: Arc<dyn HttpTransport>,
+    clock: Arc<dyn Clock>,
     config: Config,
     risk_manager: RiskManager,
+    market_monitor: Arc<MarketMonitor>,
     wallet: LocalWallet,
+    nonce_manager: NonceManager,
+    orders: OrderRegistry,
+    paper: Option<PaperMatcher>, // Routes orders to the simulated matcher instead of the CLOB
+    telemetry: Arc<Telemetry>, // Order latency, errors and partial fills for the circuit breakers
+    pub trade_tx: broadcast::Sender<TradeEvent>, // Broadcasts the outcome of every arb attempt
+}
+
+/// A signed order ready to be posted, alone or in a batch.
+struct SignedOrder {
+    order_id: String,
+    body: serde_json::Value,
+}
+
+/// Why a batch `POST /orders` produced no responses.
+enum BatchFailure {
+    Unsent(String), // Never reached the book: safe to post the legs again
+    Unknown(String), // May have reached the book
 }
 
 impl ExecutionEngine {
-    pub fn new(config: Config, risk_manager: RiskManager) -> Self {
+    /// Shares the monitor's clock and HTTP transport.
+    pub fn new(config: Config, risk_manager: RiskManager, market_monitor: Arc<MarketMonitor>) -> Self {
+        let (clock, http) = (market_monitor.clock(), market_monitor.http());
+        Self::with_transport(config, risk_manager, market_monitor, clock, http)
+    }
+
+    pub fn with_transport(config: Config, risk_manager: RiskManager, market_monitor: Arc<MarketMonitor>, clock: Arc<dyn Clock>, http: Arc<dyn HttpTransport>) -> Self {
         let wallet = LocalWallet::from_str(&config.private_key)
             .expect("Invalid private key")
             .with_chain_id(CHAIN_ID);
 
+        let paper = config.paper_trading.then(|| PaperMatcher::new(
+            market_monitor.clone(),
+            config.paper_latency_ms,
+            config.paper_slippage_bps,
+            config.paper_queue_share,
+        ));
+
         Self {
-            client: Client::new(),
+            http,
+            orders: OrderRegistry::with_clock(clock.clone()),
+            clock,
             config,
             risk_manager,
             wallet,
+            nonce_manager: NonceManager::new(0),
+            paper,
+            telemetry: market_monitor.telemetry(),
+            market_monitor,
+            trade_tx: broadcast::channel(1024).0,
+        }
+    }
+
+    fn exchange_contract(&self) -> anyhow::Result<CtfExchange<SignerMiddleware<Provider<Http>, LocalWallet>>> {
+        let rpc_url = self.config.rpc_url.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("POLY_RPC_URL not configured"))?;
+        let provider = Provider::<Http>::try_from(rpc_url)?;
+        let client = Arc::new(SignerMiddleware::new(provider, self.wallet.clone()));
+        Ok(CtfExchange::new(CTF_EXCHANGE_ADDRESS.parse::<Address>()?, client))
+    }
+
+    /// Reads our USDC collateral balance and exchange allowance from the CLOB,
+    /// falling back to the chain when the CLOB call fails and an RPC is configured.
+    pub async fn fetch_collateral(&self) -> anyhow::Result<CollateralBalance> {
+        match self.fetch_clob_collateral().await {
+            Ok(c) => Ok(c),
+            Err(e) if self.config.rpc_url.is_some() => {
+                warn!("CLOB balance lookup failed ({}), reading the chain", e);
+                self.fetch_chain_collateral().await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn fetch_clob_collateral(&self) -> anyhow::Result<CollateralBalance> {
+        let resp: BalanceAllowanceResponse = self.get_authed("/balance-allowance?asset_type=COLLATERAL&signature_type=0").await?;
+        if resp.balance.is_empty() {
+            anyhow::bail!("Balance missing from /balance-allowance response");
         }
+        // Prefer the allowance granted to the CTF Exchange; otherwise the smallest one
+        let allowance = resp.allowances.iter()
+            .find(|(spender, _)| spender.eq_ignore_ascii_case(CTF_EXCHANGE_ADDRESS))
+            .map(|(_, a)| usdc_from_base_units(a))
+            .or_else(|| resp.allowances.values().map(|a| usdc_from_base_units(a)).min())
+            .or_else(|| resp.allowance.as_deref().map(usdc_from_base_units))
+            .unwrap_or_default();
+        Ok(CollateralBalance { balance: usdc_from_base_units(&resp.balance), allowance })
     }
 
-    pub async fn execute_arb(&self, market_id: &str, yes_token: &str, no_token: &str, price_yes: Decimal, price_no: Decimal, size: Decimal) -> TradeStatus {
+    async fn fetch_chain_collateral(&self) -> anyhow::Result<CollateralBalance> {
+        let rpc_url = self.config.rpc_url.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("POLY_RPC_URL not configured"))?;
+        let provider = Arc::new(Provider::<Http>::try_from(rpc_url)?);
+        let usdc = Erc20::new(USDC_ADDRESS.parse::<Address>()?, provider);
+        let owner = self.config.funder_address.parse::<Address>()?;
+        let exchange = CTF_EXCHANGE_ADDRESS.parse::<Address>()?;
+        let balance = usdc.balance_of(owner).call().await?;
+        let allowance = usdc.allowance(owner, exchange).call().await?;
+        Ok(CollateralBalance {
+            balance: usdc_from_base_units(&balance.to_string()),
+            allowance: usdc_from_base_units(&allowance.to_string()),
+        })
+    }
+
+    /// Re-reads the collateral balance every `BALANCE_SYNC_SECS` and reconciles it
+    /// with the RiskManager. Skipped while orders are open, since their fills move
+    /// the balance mid-read.
+    pub async fn run_balance_sync(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.balance_sync_secs.max(1)));
+        interval.tick().await; // The startup sync just happened
+        loop {
+            interval.tick().await;
+            if !self.orders.open_orders(None).is_empty() {
+                debug!("Balance sync skipped: orders open");
+                continue;
+            }
+            match self.fetch_collateral().await {
+                Ok(collateral) => {
+                    self.risk_manager.reconcile_balance(collateral, self.config.balance_drift_tolerance);
+                }
+                Err(e) => warn!("Balance sync failed: {}", e),
+            }
+        }
+    }
+
+    /// Values the position ledger at current book prices (`MARK_PRICE`) and hands
+    /// the result to the RiskManager.
+    pub fn mark_to_market(&self) -> Valuation {
+        let valuation = self.risk_manager.positions().mark(|token_id| self.market_monitor.get_mark_price(token_id, self.config.mark_price));
+        self.risk_manager.record_valuation(valuation.clone());
+        valuation
+    }
+
+    /// Marks positions to market every `MARK_INTERVAL_SECS`.
+    pub async fn run_mark_to_market(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.mark_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            let valuation = self.mark_to_market();
+            if !valuation.market_value.is_zero() {
+                debug!("Marked positions at {} (unrealized {}), equity {}",
+                    valuation.market_value.round_dp(4), valuation.unrealized_pnl.round_dp(4), self.risk_manager.equity().round_dp(4));
+            }
+        }
+    }
+
+    /// Loads the current exchange nonce for our maker address from the chain.
+    pub async fn sync_exchange_nonce(&self) -> anyhow::Result<u64> {
+        let contract = self.exchange_contract()?;
+        let maker = self.config.funder_address.parse::<Address>()?;
+        let nonce = contract.nonces(maker).call().await?.as_u64();
+        self.nonce_manager.set_nonce(nonce);
+        info!("Exchange nonce synced: {}", nonce);
+        Ok(nonce)
+    }
+
+    /// Invalidates every order signed with the current nonce by calling
+    /// `incrementNonce` on the exchange, then moves new orders to the next nonce.
+    pub async fn invalidate_all_orders(&self) -> anyhow::Result<u64> {
+        if let Some(paper) = &self.paper {
+            paper.cancel(&CancelScope::All);
+            return Ok(self.nonce_manager.bump_nonce());
+        }
+        let contract = self.exchange_contract()?;
+        let call = contract.increment_nonce();
+        let pending = call.send().await?;
+        let receipt = pending.await?;
+        info!("incrementNonce mined: {:?}", receipt.map(|r| r.transaction_hash));
+        let nonce = self.nonce_manager.bump_nonce();
+        warn!("Exchange nonce bumped to {}. All previously signed orders are void.", nonce);
+        Ok(nonce)
+    }
+
+    pub async fn execute_arb(&self, market_id: &str, yes: &ArbLeg, no: &ArbLeg, size: Decimal) -> TradeStatus {
+        let mut event = TradeEvent {
+            id: uuid::Uuid::new_v4(),
+            market_id: market_id.to_string(),
+            yes_price: yes.price,
+            no_price: no.price,
+            edge: Decimal::ONE - yes.price - no.price,
+            size,
+            filled_size: Decimal::ZERO,
+            cost: (yes.price + no.price) * size,
+            pnl: Decimal::ZERO,
+            timestamp: self.clock.now(),
+            status: TradeStatus::Pending,
+            logs: Vec::new(),
+        };
+
+        let status = self.execute_arb_legs(market_id, yes, no, size, &mut event).await;
+        self.risk_manager.release(event.id);
+        event.status = status.clone();
+        let _ = self.trade_tx.send(event);
+        status
+    }
+
+    async fn execute_arb_legs(&self, market_id: &str, yes: &ArbLeg, no: &ArbLeg, size: Decimal, event: &mut TradeEvent) -> TradeStatus {
         let start = Instant::now();
+
+        if let Some(halt) = self.risk_manager.market_halt(market_id) {
+            warn!("Skipping arb on {}: SAFE MODE is active ({}).", market_id, halt.reason);
+            event.logs.push(format!("SAFE MODE active: {}", halt.reason));
+            return TradeStatus::Failed;
+        }
         
-        let total_cost = (price_yes + price_no) * size;
-        if !self.risk_manager.check_trade_size(total_cost) {
+        let (yes_token, no_token) = (yes.token_id.as_str(), no.token_id.as_str());
+        if self.risk_manager.has_unhedged_exposure(market_id) {
+            warn!("Skipping arb on {}: unhedged exposure outstanding.", market_id);
+            event.logs.push("Unhedged exposure outstanding".to_string());
             return TradeStatus::Failed;
         }
 
-        info!("Executing Arb: Market {}, Size {}, YES @ {}, NO @ {}", market_id, size, price_yes, price_no);
+        // Held until execute_arb releases it, whichever way the legs go
+        let total_cost = (yes.price + no.price) * size;
+        if !self.risk_manager.reserve(event.id, market_id, &self.market_monitor.get_market_tags(market_id), total_cost) {
+            event.logs.push("Rejected by risk check".to_string());
+            return TradeStatus::Failed;
+        }
 
-        let order_yes = self.create_order_payload(market_id, yes_token, Side::Buy, price_yes, size);
-        let order_no = self.create_order_payload(market_id, no_token, Side::Buy, price_no, size);
+        info!("Executing Arb: Market {}, Size {}, YES @ {} ({}), NO @ {} ({})", market_id, size, yes.price, yes.order_type, no.price, no.order_type);
 
-        let (res_yes, res_no) = tokio::join!(
-            self.place_order(&order_yes),
-            self.place_order(&order_no)
-        );
+        let (order_yes, order_no) = match (
+            self.create_order_payload(market_id, yes_token, Side::Buy, yes.price, size, yes.order_type),
+            self.create_order_payload(market_id, no_token, Side::Buy, no.price, size, no.order_type),
+        ) {
+            (Ok(y), Ok(n)) => (y, n),
+            (y, n) => {
+                error!("Invalid arb orders on {}: YES {:?}, NO {:?}", market_id, y.err(), n.err());
+                return TradeStatus::Failed;
+            }
+        };
+
+        let mut results = self.place_orders(&[order_yes, order_no]).await.into_iter();
+        let (res_yes, res_no) = (results.next().unwrap(), results.next().unwrap());
 
         let latency = start.elapsed();
         info!("Orders placed in {:?}. Checking fills...", latency);
 
-        let filled_yes = self.verify_fill(&order_yes, res_yes.as_ref().ok()).await;
-        let filled_no = self.verify_fill(&order_no, res_no.as_ref().ok()).await;
+        let (fill_yes, fill_no) = tokio::join!(
+            self.verify_fill(res_yes),
+            self.verify_fill(res_no)
+        );
+        info!("Fills: YES {} @ {} ({} {:?}), NO {} @ {} ({} {:?})",
+            fill_yes.filled_size, fill_yes.avg_price, fill_yes.status, fill_yes.order_id,
+            fill_no.filled_size, fill_no.avg_price, fill_no.status, fill_no.order_id);
+        event.logs.push(format!("YES {} @ {} ({})", fill_yes.filled_size, fill_yes.avg_price, fill_yes.status));
+        event.logs.push(format!("NO {} @ {} ({})", fill_no.filled_size, fill_no.avg_price, fill_no.status));
+
+        self.risk_manager.commit(event.id, fill_yes.filled_size * fill_yes.avg_price + fill_no.filled_size * fill_no.avg_price);
+
+        if !fill_yes.has_fill() && !fill_no.has_fill() {
+            info!("Both orders failed/cancelled. No exposure. YES: {:?}, NO: {:?}", fill_yes.error, fill_no.error);
+            return TradeStatus::Cancelled;
+        }
 
-        if filled_yes && filled_no {
-            info!("ARBITRAGE SUCCESS: Secured guaranteed profit.");
-            let profit = (Decimal::ONE - (price_yes + price_no)) * size;
+        // Only the matched quantity is a hedged arb; book profit on that alone
+        let matched = fill_yes.filled_size.min(fill_no.filled_size);
+        event.filled_size = matched;
+        if matched > Decimal::ZERO {
+            let profit_per_share = Decimal::ONE - fill_yes.avg_price - fill_no.avg_price;
+            let profit = profit_per_share * matched;
             self.risk_manager.record_pnl(profit);
+            event.pnl += profit;
+            // Booked against both legs: if either trade fails on-chain, so does the hedge
+            let legs: Vec<&str> = [&fill_yes.order_id, &fill_no.order_id].into_iter().flatten().map(String::as_str).collect();
+            self.orders.book_pnl(event.id, &legs, matched, profit_per_share);
+            if matched >= size {
+                info!("ARBITRAGE SUCCESS: Secured guaranteed profit.");
+            } else {
+                info!("Arb matched {}/{} on {}. Profit {}", matched, size, market_id, profit);
+            }
+        }
+
+        let imbalance = (fill_yes.filled_size - fill_no.filled_size).abs();
+        if imbalance.is_zero() {
             return TradeStatus::Filled;
-        } else if !filled_yes && !filled_no {
-            info!("Both orders failed/cancelled. No exposure.");
-            return TradeStatus::Cancelled;
+        }
+        let (exposed_token, missing_token, exposed_price) = if fill_yes.filled_size > fill_no.filled_size {
+            (yes_token, no_token, fill_yes.avg_price)
         } else {
-            error!("PARTIAL FILL EMERGENCY: YES={}, NO={}", filled_yes, filled_no);
-            self.handle_emergency(market_id, yes_token, no_token, filled_yes, filled_no, size).await;
+            (no_token, yes_token, fill_no.avg_price)
+        };
+
+        if matched.is_zero() {
+            error!("PARTIAL FILL EMERGENCY: YES={}, NO={}", fill_yes.filled_size, fill_no.filled_size);
+            event.pnl += self.handle_emergency(market_id, exposed_token, missing_token, exposed_price, imbalance).await;
             return TradeStatus::PartialFillEmergency;
         }
+
+        // Both legs traded, just not equally: even them out without risking more than breakeven
+        warn!("Leg imbalance on {}: YES={}, NO={}. Balancing {} of {}", market_id, fill_yes.filled_size, fill_no.filled_size, imbalance, exposed_token);
+        let (residual, realized) = self.rebalance(market_id, exposed_token, missing_token, exposed_price, imbalance, Decimal::ZERO).await;
+        event.pnl += realized;
+        if residual.is_zero() {
+            TradeStatus::Filled
+        } else {
+            error!("PARTIAL FILL EMERGENCY: {} of {} left unhedged after balancing", residual, exposed_token);
+            self.risk_manager.report_exposure(market_id, exposed_token, residual);
+            self.telemetry.record_partial_fill();
+            self.risk_manager.enter_safe_mode(SafeModeReason::PartialFill, SafeModeScope::Global,
+                &format!("{} of {} left unhedged after balancing on {}", residual, exposed_token, market_id));
+            TradeStatus::PartialFillEmergency
+        }
     }
 
-    fn create_order_payload(&self, market_id: &str, token_id: &str, side: Side, price: Decimal, size: Decimal) -> OrderRequest {
-        OrderRequest {
+    /// Builds an order request, enforcing the constraints of its order type.
+    fn create_order_payload(&self, market_id: &str, token_id: &str, side: Side, price: Decimal, size: Decimal, order_type: OrderType) -> Result<OrderRequest, String> {
+        if price <= Decimal::ZERO || price >= Decimal::ONE {
+            return Err(format!("Price {} outside (0, 1)", price));
+        }
+        if size <= Decimal::ZERO {
+            return Err(format!("Size {} must be positive", size));
+        }
+        if let OrderType::Gtd { expiration } = order_type {
+            // The CLOB rejects GTD orders that expire within its one-minute security threshold
+            let min_expiration = self.clock.now() + chrono::Duration::seconds(MIN_GTD_LEAD_SECS);
+            if expiration < min_expiration {
+                return Err(format!("GTD expiration {} must be after {}", expiration, min_expiration));
+            }
+        }
+
+        Ok(OrderRequest {
             market_id: market_id.to_string(),
             token_id: token_id.to_string(),
             side,
             price,
             size,
-            order_type: "FOK".to_string(),
-            nonce: chrono::Utc::now().timestamp_millis() as u64, // Usually better to use a dedicated nonce manager
-        }
+            order_type,
+            salt: self.nonce_manager.next_salt(),
+            nonce: self.nonce_manager.current_nonce(),
+        })
     }
 
-    async fn place_order(&self, order_req: &OrderRequest) -> Result<String, String> {
-        let url = format!("{}/order", self.config.http_url);
-        
+    /// Signs an order and registers it, returning its id (the EIP-712 order hash) and request body.
+    async fn sign_order(&self, order_req: &OrderRequest) -> Result<SignedOrder, String> {
         // 1. Construct EIP-712 Order Struct
         // Map Decimal to U256 (Assuming 6 decimals for USDC collateral / CTF)
         let maker_amount = U256::from((order_req.size * Decimal::new(1_000_000, 0)).to_u64().unwrap_or(0));
@@ -102,24 +393,32 @@ impl ExecutionEngine {
         };
 
         let order = Order {
-            salt: U256::from(order_req.nonce),
+            salt: U256::from(order_req.salt),
             maker: self.config.funder_address.parse::<Address>().unwrap_or_default(),
             signer: self.wallet.address(),
             taker: Address::zero(),
             tokenId: U256::from_dec_str(&order_req.token_id).unwrap_or_default(),
             makerAmount: maker_amount,
             takerAmount: taker_amount,
-            expiration: U256::zero(),
-            nonce: U256::from(0), // Exchange nonce, often 0 for new orders if not tracking on-chain
+            expiration: U256::from(order_req.order_type.expiration_secs()),
+            nonce: U256::from(order_req.nonce),
             feeRateBps: U256::zero(),
             side: side_val,
             signatureType: 0, // 0=EOA, 1=PolyProxy. Using 0 for direct EOA or 1 if using proxy wallet.
         };
 
-        let signature = self.wallet.sign_typed_data(&order).await.map_err(|e| e.to_string())?;
-        
-        // 2. Build HTTP Headers
-        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let order_hash = order.encode_eip712().map_err(|e| e.to_string())?;
+        let order_id = format!("0x{}", hex::encode(order_hash));
+        self.orders.create(&order_id, order_req);
+
+        let signature = match self.wallet.sign_typed_data(&order).await {
+            Ok(sig) => sig,
+            Err(e) => {
+                self.track(self.orders.reject(&order_id, e.to_string()));
+                return Err(e.to_string());
+            }
+        };
+        self.track(self.orders.transition(&order_id, OrderState::Signed));
         
         let side_str = match order_req.side {
             Side::Buy => "BUY",
@@ -131,60 +430,712 @@ impl ExecutionEngine {
             "price": order_req.price.to_string(),
             "size": order_req.size.to_string(),
             "side": side_str,
-            "order_type": "FOK",
-            "expiration": 0,
+            "order_type": order_req.order_type.as_str(),
+            "expiration": order_req.order_type.expiration_secs(),
             "signature": format!("0x{}", signature)
         });
 
-        // 3. Send Request
+        Ok(SignedOrder { order_id, body })
+    }
+
+    /// POSTs an authenticated JSON body, returning the response on HTTP success.
+    async fn post_authed(&self, path: &str, body: &str) -> Result<HttpResponse, String> {
+        // Build L2 Auth Headers (signed over the exact body we send)
+        let headers = self.auth_headers("POST", path, body).map_err(|e| e.to_string())?;
+
         // ENABLED: Sending real orders to Polymarket CLOB
-        let resp = self.client.post(&url)
-            .header("POLY-API-KEY", &self.config.api_key)
-            .header("POLY-API-TIMESTAMP", &timestamp)
-            .header("POLY-API-PASSPHRASE", &self.config.api_passphrase)
-            .header("POLY-API-SIGN", "mock_hmac_sig") // You need to implement actual HMAC signature if not using Proxy-signed body
-            .json(&body)
-            .send()
-            .await;
-            
-        match resp {
-            Ok(r) => {
-                if r.status().is_success() {
-                    let res_json: serde_json::Value = r.json().await.unwrap_or_default();
-                    info!("Order Success: {:?}", res_json);
-                    Ok(res_json["id"].as_str().unwrap_or("unknown").to_string())
-                } else {
-                    let err_text = r.text().await.unwrap_or_default();
-                    error!("Order Failed: {} | Body: {}", err_text, body);
-                    Err(format!("HTTP Error: {}", err_text))
+        let resp = self.http.send(HttpRequest::post(format!("{}{}", self.config.http_url, path), body).headers(headers))
+            .await
+            .map_err(|e| format!("Network Error: {}", e))?;
+
+        if resp.is_success() {
+            Ok(resp)
+        } else {
+            error!("POST {} Failed: {} | Body: {}", path, resp.body, body);
+            Err(format!("HTTP Error: {}", resp.body))
+        }
+    }
+
+    /// Signs and submits a single order, tracking it in the registry.
+    ///
+    /// Returns the order id once the exchange has accepted it.
+    async fn place_order(&self, order_req: &OrderRequest) -> Result<String, String> {
+        let signed = self.sign_order(order_req).await?;
+        self.submit_order(order_req, &signed).await
+    }
+
+    async fn submit_order(&self, order_req: &OrderRequest, signed: &SignedOrder) -> Result<String, String> {
+        let order_id = &signed.order_id;
+        if self.orders.get(order_id).is_some_and(|o| o.state == OrderState::Signed) {
+            self.track(self.orders.transition(order_id, OrderState::Submitted));
+        }
+
+        let result = match &self.paper {
+            Some(paper) => Ok(paper.submit(order_id, order_req).await),
+            None => {
+                let sent = tokio::time::Instant::now();
+                let result = match self.post_authed("/order", &signed.body.to_string()).await {
+                    Ok(r) => r.json::<OrderResponse>().map_err(|e| format!("Bad order response: {}", e)),
+                    Err(e) => Err(e),
+                };
+                self.telemetry.record_order(sent.elapsed(), result.is_ok());
+                result
+            }
+        };
+        self.finish_submission(order_req, order_id, result)
+    }
+
+    /// Signs all legs and submits them in one `POST /orders`, so no leg can race
+    /// ahead of another. Results come back in the same order as `order_reqs`.
+    ///
+    /// If the batch surely never reached the book, the already-signed legs are posted
+    /// one by one. If it may have, each leg is left for `verify_fill` to look up.
+    async fn place_orders(&self, order_reqs: &[OrderRequest]) -> Vec<Result<String, String>> {
+        let signed: Vec<Result<SignedOrder, String>> = join_all(order_reqs.iter().map(|r| self.sign_order(r))).await;
+
+        if !self.config.batch_orders || order_reqs.len() < 2 {
+            return join_all(order_reqs.iter().zip(signed).map(|(req, s)| async move {
+                self.submit_order(req, &s?).await
+            })).await;
+        }
+
+        let legs: Vec<(&OrderRequest, &SignedOrder)> = order_reqs.iter().zip(&signed)
+            .filter_map(|(req, s)| s.as_ref().ok().map(|s| (req, s)))
+            .collect();
+        for (_, s) in &legs {
+            self.track(self.orders.transition(&s.order_id, OrderState::Submitted));
+        }
+
+        let batch = if let Some(paper) = &self.paper {
+            Ok(join_all(legs.iter().map(|(req, s)| paper.submit(&s.order_id, req))).await)
+        } else {
+            let body = serde_json::Value::Array(legs.iter().map(|(_, s)| s.body.clone()).collect()).to_string();
+            self.post_batch(&body).await
+        };
+
+        let responses = match batch {
+            Ok(responses) => responses,
+            Err(BatchFailure::Unsent(e)) => {
+                warn!("Batch submission failed ({}), falling back to individual orders", e);
+                return join_all(order_reqs.iter().zip(signed).map(|(req, s)| async move {
+                    self.submit_order(req, &s?).await
+                })).await;
+            }
+            Err(BatchFailure::Unknown(e)) => {
+                // Posting again could double up; verify_fill finds out what reached the book
+                warn!("Batch submission outcome unknown ({}), looking each leg up", e);
+                return signed.into_iter().map(|s| s.map(|s| s.order_id)).collect();
+            }
+        };
+
+        let order_ids: Vec<&str> = legs.iter().map(|(_, s)| s.order_id.as_str()).collect();
+        let mut matched = correlate_batch(&order_ids, responses).into_iter();
+        let mut results = Vec::with_capacity(order_reqs.len());
+        for (req, s) in order_reqs.iter().zip(signed) {
+            let s = match s {
+                Ok(s) => s,
+                Err(e) => {
+                    results.push(Err(e));
+                    continue;
                 }
-            },
-            Err(e) => Err(format!("Network Error: {}", e))
+            };
+            match matched.next().flatten() {
+                Some(resp) => results.push(self.finish_submission(req, &s.order_id, Ok(resp))),
+                None => {
+                    // It may still have reached the book; verify_fill looks it up
+                    warn!("No response for order {} in batch", s.order_id);
+                    results.push(Ok(s.order_id));
+                }
+            }
         }
+        results
     }
 
-    async fn verify_fill(&self, _order: &OrderRequest, _order_id: Option<&String>) -> bool {
-        if let Some(_) = _order_id {
-            return true; 
+    /// Sends a batch `POST /orders`. Only a refused connection or a 4xx is
+    /// `Unsent`: after a timeout, a 5xx or an unreadable reply the legs may be live.
+    async fn post_batch(&self, body: &str) -> Result<Vec<OrderResponse>, BatchFailure> {
+        let headers = self.auth_headers("POST", "/orders", body).map_err(|e| BatchFailure::Unsent(e.to_string()))?;
+        let sent = tokio::time::Instant::now();
+        let result = match self.http.send(HttpRequest::post(format!("{}/orders", self.config.http_url), body).headers(headers)).await {
+            Err(e) if e.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_connect()) => {
+                Err(BatchFailure::Unsent(format!("Network Error: {}", e)))
+            }
+            Err(e) => Err(BatchFailure::Unknown(format!("Network Error: {}", e))),
+            Ok(resp) if resp.is_success() => {
+                resp.json::<Vec<OrderResponse>>().map_err(|e| BatchFailure::Unknown(format!("Bad batch response: {}", e)))
+            }
+            Ok(resp) => {
+                error!("POST /orders Failed: {} {} | Body: {}", resp.status, resp.body, body);
+                let e = format!("HTTP Error {}: {}", resp.status, resp.body);
+                Err(if (400..500).contains(&resp.status) { BatchFailure::Unsent(e) } else { BatchFailure::Unknown(e) })
+            }
+        };
+        self.telemetry.record_order(sent.elapsed(), result.is_ok());
+        result
+    }
+
+    fn finish_submission(&self, order_req: &OrderRequest, order_id: &str, result: Result<OrderResponse, String>) -> Result<String, String> {
+        match result {
+            Ok(order_resp) => {
+                info!("Order Response: {:?}", order_resp);
+                self.apply_order_response(order_id, order_req, &order_resp)?;
+                Ok(order_id.to_string())
+            }
+            Err(e) => {
+                self.track(self.orders.reject(order_id, e.clone()));
+                Err(e)
+            }
         }
-        false
     }
 
-    async fn handle_emergency(&self, market_id: &str, yes_token: &str, no_token: &str, filled_yes: bool, _filled_no: bool, size: Decimal) {
-        self.risk_manager.enter_safe_mode();
-        
-        let (token_to_dump, _token_missing) = if filled_yes {
-            (yes_token, no_token)
+    /// Folds the `POST /order` response into the registry. Errors if the exchange refused the order.
+    fn apply_order_response(&self, order_id: &str, order_req: &OrderRequest, resp: &OrderResponse) -> Result<(), String> {
+        if !resp.success || !resp.error_msg.is_empty() {
+            self.track(self.orders.reject(order_id, resp.error_msg.clone()));
+            return Err(format!("Order rejected: {}", resp.error_msg));
+        }
+        if !resp.order_id.is_empty() && resp.order_id != order_id {
+            warn!("Exchange order id {} differs from local hash {}", resp.order_id, order_id);
+        }
+
+        let making = parse_amount(&resp.making_amount);
+        let taking = parse_amount(&resp.taking_amount);
+        if making > Decimal::ZERO && taking > Decimal::ZERO {
+            // BUY: we make USDC and take shares. SELL: the reverse.
+            let (shares, usdc) = match order_req.side {
+                Side::Buy => (taking, making),
+                Side::Sell => (making, taking),
+            };
+            self.track(self.orders.record_matched(order_id, shares, Some(usdc)));
+        }
+
+        match OrderState::from_clob_status(&resp.status) {
+            // Without amounts we don't know yet; verify_fill will look the order up.
+            Some(OrderState::Filled) => self.close_killed_remainder(order_id),
+            Some(state) => {
+                if self.orders.get(order_id).is_some_and(|o| o.state != state) {
+                    self.track(self.orders.transition(order_id, state));
+                }
+            }
+            None => warn!("Unknown order status '{}' for {}", resp.status, order_id),
+        }
+        Ok(())
+    }
+
+    /// On MATCHED, a FOK/FAK order that filled only in part had the rest killed, so it
+    /// is done; a GTC/GTD remainder keeps resting.
+    fn close_killed_remainder(&self, order_id: &str) {
+        if self.orders.get(order_id).is_some_and(|o| o.order_type.is_immediate() && o.state == OrderState::PartiallyFilled) {
+            self.track(self.orders.transition(order_id, OrderState::Cancelled));
+        }
+    }
+
+    /// Registry updates can race between REST and the user channel; losing one is not fatal.
+    fn track(&self, result: Result<(), String>) {
+        if let Err(e) = result {
+            debug!("Order registry: {}", e);
+        }
+    }
+
+    pub fn orders(&self) -> &OrderRegistry {
+        &self.orders
+    }
+
+    /// Forgets orders finished more than `RETENTION_SECS` ago, in the registry
+    /// and, on paper, in the simulated exchange.
+    pub fn prune(&self, now: DateTime<Utc>) {
+        self.orders.prune(now);
+        if let Some(paper) = &self.paper {
+            paper.prune(now);
+        }
+    }
+
+    /// Cancels a single resting order.
+    pub async fn cancel_order(&self, order_id: &str) -> anyhow::Result<CancelResponse> {
+        self.send_cancel(CancelScope::Orders(vec![order_id.to_string()])).await
+    }
+
+    /// Cancels several orders in one request.
+    pub async fn cancel_orders(&self, order_ids: &[String]) -> anyhow::Result<CancelResponse> {
+        self.send_cancel(CancelScope::Orders(order_ids.to_vec())).await
+    }
+
+    /// Cancels every order in a market, optionally only for one of its tokens.
+    pub async fn cancel_market(&self, market_id: &str, asset_id: Option<&str>) -> anyhow::Result<CancelResponse> {
+        self.send_cancel(CancelScope::Market {
+            market_id: market_id.to_string(),
+            asset_id: asset_id.map(str::to_string),
+        }).await
+    }
+
+    /// Cancels every open order on the account, falling back to a batch cancel
+    /// of the orders we know about if the exchange-wide call fails.
+    pub async fn cancel_all(&self) -> anyhow::Result<CancelResponse> {
+        match self.send_cancel(CancelScope::All).await {
+            Ok(resp) => Ok(resp),
+            Err(e) => {
+                warn!("cancel-all failed ({}), cancelling tracked orders individually", e);
+                let ids: Vec<String> = self.orders.open_orders(None).into_iter().map(|o| o.order_id).collect();
+                if ids.is_empty() {
+                    return Err(e);
+                }
+                self.cancel_orders(&ids).await
+            }
+        }
+    }
+
+    async fn send_cancel(&self, scope: CancelScope) -> anyhow::Result<CancelResponse> {
+        let resp = match &self.paper {
+            Some(paper) => paper.cancel(&scope),
+            None => {
+                let (path, body) = match &scope {
+                    CancelScope::Orders(ids) if ids.len() == 1 => ("/order", json!({ "orderID": ids[0] }).to_string()),
+                    CancelScope::Orders(ids) => ("/orders", json!(ids).to_string()),
+                    CancelScope::Market { market_id, asset_id } => {
+                        let mut body = json!({ "market": market_id });
+                        if let Some(asset_id) = asset_id {
+                            body["asset_id"] = json!(asset_id);
+                        }
+                        ("/cancel-market-orders", body.to_string())
+                    }
+                    CancelScope::All => ("/cancel-all", String::new()),
+                };
+                let headers = self.auth_headers("DELETE", path, &body)?;
+                self.http.send(HttpRequest::delete(format!("{}{}", self.config.http_url, path), body).headers(headers))
+                    .await?
+                    .error_for_status()?
+                    .json::<CancelResponse>()?
+            }
+        };
+
+        for order_id in &resp.canceled {
+            if self.orders.get(order_id).is_some_and(|o| !o.state.is_terminal()) {
+                self.track(self.orders.transition(order_id, OrderState::Cancelled));
+            }
+        }
+        for (order_id, reason) in &resp.not_canceled {
+            warn!("Order {} not cancelled: {}", order_id, reason);
+        }
+        info!("Cancel {:?}: {} cancelled, {} refused", scope, resp.canceled.len(), resp.not_canceled.len());
+        Ok(resp)
+    }
+
+    fn auth_headers(&self, method: &str, path: &str, body: &str) -> anyhow::Result<HeaderMap> {
+        let address = to_checksum(&self.wallet.address(), None);
+        l2_headers(&self.config, &address, self.clock.now().timestamp(), method, path, body)
+    }
+
+    async fn get_authed<T: DeserializeOwned>(&self, path_and_query: &str) -> anyhow::Result<T> {
+        // The signature covers the path only, not the query string.
+        let path = path_and_query.split('?').next().unwrap_or(path_and_query);
+        let headers = self.auth_headers("GET", path, "")?;
+        let resp = self.http.send(HttpRequest::get(format!("{}{}", self.config.http_url, path_and_query)).headers(headers))
+            .await?
+            .error_for_status()?;
+        resp.json::<T>()
+    }
+
+    /// Works out how much of an order actually filled and at what average price.
+    ///
+    /// The POST response and the user channel usually settle this already.
+    /// Otherwise (delayed matching, missing amounts) the order is looked up directly.
+    async fn verify_fill(&self, placed: Result<String, String>) -> FillReport {
+        let order_id = match placed {
+            Ok(id) => id,
+            Err(e) => return FillReport::rejected(None, e),
+        };
+        let report = match self.orders.get(&order_id) {
+            Some(order) if order.state.is_terminal() => order.fill_report(),
+            Some(_) => self.query_fill(&order_id).await,
+            None => return FillReport::rejected(Some(order_id), "Order missing from registry".to_string()),
+        };
+        self.on_fill_confirmed(&order_id, &report);
+        report
+    }
+
+    /// Books the cash and shares moved by a confirmed fill. Whatever the user
+    /// channel reported while the fill was being checked is booked with it.
+    fn on_fill_confirmed(&self, order_id: &str, report: &FillReport) {
+        let Some(order) = self.orders.get(order_id) else { return };
+        if report.has_fill() {
+            self.risk_manager.record_fill(&order.market_id, &order.token_id, &order.side, report.filled_size, report.avg_price);
+        }
+        self.track(self.orders.record_booked_fill(order_id, report.filled_size));
+        self.book_late_fill(order_id, None);
+    }
+
+    /// Books fill an order gained after `verify_fill` was done with it, e.g. a
+    /// resting leg whose cancel lost the race with a match, at `price` if the
+    /// trade gave one. Any exposure it leaves unhedged is reported.
+    fn book_late_fill(&self, order_id: &str, price: Option<Decimal>) {
+        let Some((order, size)) = self.orders.take_unbooked(order_id) else { return };
+        let price = price.unwrap_or_else(|| order.avg_price());
+        warn!("Late fill of {} {:?} {} @ {} on order {}", size, order.side, order.token_id, price, order_id);
+        self.report_unhedged(&order.market_id, || {
+            self.risk_manager.record_fill(&order.market_id, &order.token_id, &order.side, size, price);
+        });
+    }
+
+    /// Applies `change` to risk and reports any exposure it leaves unhedged in `market_id`.
+    fn report_unhedged(&self, market_id: &str, change: impl FnOnce()) {
+        let Some(tokens) = self.market_monitor.get_market_tokens(market_id) else {
+            change();
+            return;
+        };
+        let net = || self.risk_manager.positions().market_exposure(&tokens.0, &tokens.1).net;
+        let before = net();
+        change();
+        let after = net();
+        let unhedged = after.abs() - before.abs();
+        if unhedged > Decimal::ZERO {
+            let exposed = if after > Decimal::ZERO { &tokens.0 } else { &tokens.1 };
+            self.risk_manager.report_exposure(market_id, exposed, unhedged);
+        }
+    }
+
+    /// Books rebalance profit (or loss) against the order that realized it.
+    fn book_order_pnl(&self, fill: &FillReport, pnl_per_share: Decimal) {
+        if let Some(order_id) = &fill.order_id {
+            self.orders.book_pnl(uuid::Uuid::new_v4(), &[order_id], fill.filled_size, pnl_per_share);
+        }
+    }
+
+    async fn query_fill(&self, order_id: &str) -> FillReport {
+        let mut last_err = String::new();
+        for attempt in 0..FILL_QUERY_ATTEMPTS {
+            if attempt > 0 {
+                sleep(Duration::from_millis(FILL_QUERY_DELAY_MS)).await;
+            }
+            // The user channel usually knows before REST does
+            if let Some(order) = self.orders.get(order_id).filter(|o| o.state.is_terminal()) {
+                return order.fill_report();
+            }
+            let open = match self.fetch_order(order_id).await {
+                Ok(o) => o,
+                Err(e) => {
+                    last_err = e.to_string();
+                    continue;
+                }
+            };
+
+            let matched = parse_amount(&open.size_matched);
+            if matched > Decimal::ZERO {
+                self.sync_trades(order_id, &open.associate_trades).await;
+                self.track(self.orders.record_matched(order_id, matched, None));
+            }
+            match OrderState::from_clob_status(&open.status) {
+                // Full fills got there through record_matched
+                Some(OrderState::Filled) => self.close_killed_remainder(order_id),
+                Some(OrderState::Live) => {}
+                Some(state) => self.track(self.orders.transition(order_id, state)),
+                None => warn!("Unknown order status '{}' for {}", open.status, order_id),
+            }
+        }
+
+        match self.orders.get(order_id) {
+            Some(order) if order.state.is_terminal() => order.fill_report(),
+            Some(order) => {
+                // An arb leg resting on the book (GTC/GTD) is no longer an arb; pull it and take the fill so far
+                warn!("Order {} still {} after {} checks ({}); cancelling", order_id, order.state, FILL_QUERY_ATTEMPTS, last_err);
+                if let Err(e) = self.cancel_order(order_id).await {
+                    error!("Failed to cancel lingering order {}: {}", order_id, e);
+                }
+                self.orders.get(order_id).unwrap_or(order).fill_report()
+            }
+            None => FillReport::rejected(Some(order_id.to_string()), last_err),
+        }
+    }
+
+    async fn fetch_order(&self, order_id: &str) -> anyhow::Result<OpenOrder> {
+        match &self.paper {
+            Some(paper) => paper.order(order_id).ok_or_else(|| anyhow::anyhow!("Unknown paper order {}", order_id)),
+            None => self.get_authed(&format!("/data/order/{}", order_id)).await,
+        }
+    }
+
+    /// Applies an order event from the user channel.
+    pub fn on_order_event(&self, event: &WsOrderEvent) {
+        if self.orders.get(&event.id).is_none() {
+            debug!("Ignoring order event for untracked order {}", event.id);
+            return;
+        }
+        match event.event.as_str() {
+            "PLACEMENT" => self.track(self.orders.transition(&event.id, OrderState::Live)),
+            "UPDATE" => {
+                self.track(self.orders.record_matched(&event.id, parse_amount(&event.size_matched), None));
+                self.book_late_fill(&event.id, None);
+            }
+            "CANCELLATION" => self.track(self.orders.transition(&event.id, OrderState::Cancelled)),
+            other => debug!("Unhandled order event type {}", other),
+        }
+    }
+
+    /// Applies a trade event from the user channel to whichever of our orders took part.
+    pub fn on_trade_event(&self, event: &WsTradeEvent) {
+        let legs: Vec<(&str, Decimal, Decimal)> = if event.trader_side == "TAKER" {
+            vec![(event.taker_order_id.as_str(), parse_amount(&event.size), parse_amount(&event.price))]
         } else {
-            (no_token, yes_token)
+            // Other makers can share the trade; only our own entries count
+            event.maker_orders.iter()
+                .filter(|m| m.owner == self.config.api_key)
+                .map(|m| (m.order_id.as_str(), parse_amount(&m.matched_amount), parse_amount(&m.price)))
+                .collect()
         };
 
-        warn!("EMERGENCY: Dumping exposure on token {}", token_to_dump);
-        
-        let dump_order = self.create_order_payload(market_id, token_to_dump, Side::Sell, Decimal::ZERO, size);
-        let _ = self.place_order(&dump_order).await;
-        
-        error!("Emergency flatten sequence complete. Trading HALTED.");
-        sleep(Duration::from_secs(60)).await;
+        for (order_id, size, price) in legs {
+            self.track(self.orders.record_trade(order_id, &event.id, size, price));
+            self.book_late_fill(order_id, Some(price));
+        }
+        self.apply_settlement(&event.id, &event.status);
+    }
+
+    /// Advances one of our trades through settlement, unwinding it if it failed on-chain.
+    fn apply_settlement(&self, trade_id: &str, status: &str) {
+        if self.orders.settlement(trade_id).is_none() {
+            return;
+        }
+        let Some(status) = SettlementStatus::from_clob_status(status) else {
+            warn!("Unknown trade status '{}' for {}", status, trade_id);
+            return;
+        };
+        match self.orders.settle(trade_id, status) {
+            Ok(Some(failed)) => self.unwind_failed_trade(&failed),
+            result => self.track(result.map(|_| ())),
+        }
+    }
+
+    /// Takes a failed trade's fills, and the profit booked on them, back out of
+    /// risk, then reports whatever the failure left unhedged.
+    fn unwind_failed_trade(&self, settlement: &TradeSettlement) {
+        let mut pnl = Decimal::ZERO;
+        for leg in &settlement.legs {
+            let Some(order) = self.orders.get(&leg.order_id) else { continue };
+            let reversal = self.orders.reverse(&leg.order_id, leg.size);
+            self.report_unhedged(&order.market_id, || {
+                if reversal.shares > Decimal::ZERO {
+                    self.risk_manager.reverse_fill(&order.token_id, &order.side, reversal.shares, leg.price);
+                }
+            });
+            pnl += reversal.pnl;
+            error!("Trade {} FAILED: reversed {} of {} {:?} {} @ {} (PnL {})",
+                settlement.trade_id, reversal.shares, leg.size, order.side, order.token_id, leg.price, reversal.pnl);
+        }
+        if !pnl.is_zero() {
+            self.risk_manager.record_pnl(-pnl);
+        }
+    }
+
+    /// Records our side of each trade in the registry.
+    async fn sync_trades(&self, order_id: &str, trade_ids: &[String]) {
+        for trade_id in trade_ids {
+            let trades: Vec<ClobTrade> = match self.get_authed(&format!("/data/trades?id={}", trade_id)).await {
+                Ok(t) => t,
+                Err(e) => {
+                    warn!("Trade lookup {} failed: {}", trade_id, e);
+                    return;
+                }
+            };
+            for trade in trades {
+                if trade.taker_order_id == order_id {
+                    self.track(self.orders.record_trade(order_id, trade_id, parse_amount(&trade.size), parse_amount(&trade.price)));
+                } else if let Some(fill) = trade.maker_orders.iter().find(|m| m.order_id == order_id) {
+                    self.track(self.orders.record_trade(order_id, trade_id, parse_amount(&fill.matched_amount), parse_amount(&fill.price)));
+                }
+                if !trade.status.is_empty() {
+                    self.apply_settlement(trade_id, &trade.status);
+                }
+            }
+        }
+    }
+
+    /// Recovers from a one-sided fill of `qty` shares of `exposed_token` bought at `exposed_price`.
+    ///
+    /// Halts trading, pulls resting orders, then rebalances within `emergency_max_loss`.
+    /// Anything still unhedged afterwards is reported to the RiskManager as exposure.
+    /// Returns the PnL the rebalance realized.
+    async fn handle_emergency(&self, market_id: &str, exposed_token: &str, missing_token: &str, exposed_price: Decimal, qty: Decimal) -> Decimal {
+        self.telemetry.record_partial_fill();
+        self.risk_manager.enter_safe_mode(SafeModeReason::PartialFill, SafeModeScope::Global,
+            &format!("One-sided fill of {} {} on {}", qty, exposed_token, market_id));
+
+        // Pull anything still resting in this market before flattening
+        if let Err(e) = self.cancel_market(market_id, None).await {
+            warn!("Could not cancel orders in market {}: {}", market_id, e);
+        }
+
+        let (remaining, realized) = self.rebalance(market_id, exposed_token, missing_token, exposed_price, qty, self.config.emergency_max_loss).await;
+        if remaining > Decimal::ZERO {
+            self.risk_manager.report_exposure(market_id, exposed_token, remaining);
+            error!("EMERGENCY: {} shares of {} remain unhedged. Trading HALTED.", remaining, exposed_token);
+        } else {
+            error!("Emergency flatten complete. Trading HALTED.");
+        }
+        realized
+    }
+
+    /// Evens out `qty` excess shares of `exposed_token` bought at `exposed_price`.
+    ///
+    /// First tops up the missing leg, as long as the completed pairs lose no more than
+    /// `max_loss`. Whatever is still excess is then trimmed by walking down the bid ladder,
+    /// never below the price at which it would lose more than what is left of `max_loss`,
+    /// nor below `emergency_min_sell_price`. The realized result is booked with the
+    /// RiskManager. Returns the quantity that could not be balanced and the PnL realized.
+    async fn rebalance(&self, market_id: &str, exposed_token: &str, missing_token: &str, exposed_price: Decimal, qty: Decimal, max_loss: Decimal) -> (Decimal, Decimal) {
+        if qty <= Decimal::ZERO {
+            return (Decimal::ZERO, Decimal::ZERO);
+        }
+        let mut realized = Decimal::ZERO;
+        let mut remaining = qty;
+
+        // 1. Top up the missing leg: paying up to 1 + max_loss/qty for both legs
+        let completion_limit = (Decimal::ONE + max_loss / qty - exposed_price)
+            .min(MAX_PRICE)
+            .round_dp_with_strategy(2, RoundingStrategy::ToZero);
+        let best_ask = self.market_monitor.get_book(missing_token)
+            .and_then(|b| b.asks.iter().map(|l| l.price).min());
+
+        match best_ask {
+            Some(ask) if ask <= completion_limit => {
+                warn!("Completing missing leg {} x {} up to {}", missing_token, remaining, completion_limit);
+                let fill = self.fak_order(market_id, missing_token, Side::Buy, completion_limit, remaining).await;
+                if fill.has_fill() {
+                    self.risk_manager.clear_exposure(market_id, exposed_token, fill.filled_size);
+                    self.book_order_pnl(&fill, Decimal::ONE - exposed_price - fill.avg_price);
+                    realized += (Decimal::ONE - exposed_price - fill.avg_price) * fill.filled_size;
+                    remaining -= fill.filled_size;
+                }
+            }
+            _ => warn!("Missing leg {} not available within max loss {} (ask {:?}, limit {})", missing_token, max_loss, best_ask, completion_limit),
+        }
+
+        // 2. Trim what is still excess, selling no lower than the loss budget left allows
+        let budget = (max_loss + realized.min(Decimal::ZERO)).max(Decimal::ZERO);
+        let floor = if remaining > Decimal::ZERO {
+            (exposed_price - budget / remaining).round_dp_with_strategy(2, RoundingStrategy::AwayFromZero)
+        } else {
+            exposed_price
+        };
+        let (left, trimmed) = self.sell_down(market_id, exposed_token, exposed_price, remaining, floor).await;
+        remaining = left;
+        realized += trimmed;
+
+        if realized != Decimal::ZERO {
+            self.risk_manager.record_pnl(realized);
+        }
+        info!("Rebalanced {} of {} on {}: realized {}", qty - remaining, qty, exposed_token, realized);
+        (remaining, realized)
+    }
+
+    /// Sells `qty` of `token_id` bought at `cost` into the bids, one level at a
+    /// time and no lower than `floor` or `emergency_min_sell_price`. Returns what
+    /// is left unsold and the PnL realized, which the caller books.
+    async fn sell_down(&self, market_id: &str, token_id: &str, cost: Decimal, qty: Decimal, floor: Decimal) -> (Decimal, Decimal) {
+        let floor = floor.max(self.config.emergency_min_sell_price);
+        let mut realized = Decimal::ZERO;
+        let mut remaining = qty;
+        let mut steps = 0;
+        while remaining > Decimal::ZERO && steps < self.config.emergency_max_steps {
+            steps += 1;
+            let Some(book) = self.market_monitor.get_book(token_id) else {
+                error!("No book for {}, cannot trim", token_id);
+                break;
+            };
+            let mut bids: Vec<Level> = book.bids.into_iter().filter(|l| l.price >= floor).collect();
+            bids.sort_by_key(|l| std::cmp::Reverse(l.price));
+
+            // Go just deep enough in the ladder to cover what we hold
+            let mut depth = Decimal::ZERO;
+            let Some(limit) = bids.iter().find(|l| {
+                depth += l.size;
+                depth >= remaining
+            }).or(bids.last()).map(|l| l.price) else {
+                error!("No bids on {} at or above {}", token_id, floor);
+                break;
+            };
+
+            warn!("Selling {} x {} down to {} (step {})", token_id, remaining, limit, steps);
+            let fill = self.fak_order(market_id, token_id, Side::Sell, limit, remaining).await;
+            if fill.has_fill() {
+                self.risk_manager.clear_exposure(market_id, token_id, fill.filled_size);
+                self.book_order_pnl(&fill, fill.avg_price - cost);
+                realized += (fill.avg_price - cost) * fill.filled_size;
+                remaining -= fill.filled_size;
+            } else {
+                sleep(Duration::from_millis(REBALANCE_STEP_DELAY_MS)).await;
+            }
+        }
+        (remaining, realized)
+    }
+
+    /// Sells every position we hold, for the kill switch. Returns the shares left unsold.
+    ///
+    /// Complete YES+NO sets already booked their arb profit, as if redeemed at 1 USDC,
+    /// so their shares are sold against that value rather than what they cost.
+    pub async fn flatten(&self) -> Decimal {
+        let ledger = self.risk_manager.positions();
+        let mut markets: Vec<String> = ledger.positions().into_iter()
+            .filter(|p| p.quantity > Decimal::ZERO)
+            .map(|p| p.market_id)
+            .collect();
+        markets.dedup();
+
+        let mut unsold = Decimal::ZERO;
+        for market_id in markets {
+            let Some((yes, no)) = self.market_monitor.get_market_tokens(&market_id) else {
+                error!("Unknown tokens for market {}, cannot flatten it", market_id);
+                continue;
+            };
+            let (yes, no) = (ledger.position(&yes).unwrap_or_default(), ledger.position(&no).unwrap_or_default());
+            let sets = yes.quantity.min(no.quantity);
+            let set_margin = (Decimal::ONE - yes.avg_cost - no.avg_cost) / Decimal::TWO;
+
+            let mut realized = Decimal::ZERO;
+            for position in [yes, no].into_iter().filter(|p| p.quantity > Decimal::ZERO) {
+                let cost = (position.cost_basis() + sets * set_margin) / position.quantity;
+                warn!("Flattening {} of {} in {}", position.quantity, position.token_id, market_id);
+                let (left, sold) = self.sell_down(&market_id, &position.token_id, cost, position.quantity, Decimal::ZERO).await;
+                unsold += left;
+                realized += sold;
+            }
+            if realized != Decimal::ZERO {
+                self.risk_manager.record_pnl(realized);
+            }
+        }
+        unsold
+    }
+
+    async fn fak_order(&self, market_id: &str, token_id: &str, side: Side, price: Decimal, size: Decimal) -> FillReport {
+        let order = match self.create_order_payload(market_id, token_id, side, price, size, OrderType::Fak) {
+            Ok(o) => o,
+            Err(e) => {
+                error!("Rebalance order invalid: {}", e);
+                return FillReport::rejected(None, e);
+            }
+        };
+        let placed = self.place_order(&order).await;
+        self.verify_fill(placed).await
+    }
+}
+
+/// Pairs a batch response with each of `order_ids`, in order. A response that
+/// echoes an id goes to that order; one without an id, or with an exchange id
+/// none of ours match, goes to the order at its position. Each response is used
+/// at most once, and an order left without one gets `None`.
+pub fn correlate_batch(order_ids: &[&str], responses: Vec<OrderResponse>) -> Vec<Option<OrderResponse>> {
+    let mut responses: Vec<Option<OrderResponse>> = responses.into_iter().map(Some).collect();
+    let mut matched: Vec<Option<OrderResponse>> = order_ids.iter()
+        .map(|id| {
+            let idx = responses.iter().position(|r| r.as_ref().is_some_and(|r| r.order_id == *id))?;
+            responses[idx].take()
+        })
+        .collect();
+    for (idx, slot) in matched.iter_mut().enumerate() {
+        if slot.is_none() {
+            *slot = responses.get_mut(idx).and_then(|r| r.take_if(|r| r.order_id.is_empty() || !order_ids.contains(&r.order_id.as_str())));
+            if let Some(resp) = slot.as_ref().filter(|r| !r.order_id.is_empty()) {
+                warn!("Batch response id {} matches no order; taking it for {} by position", resp.order_id, order_ids[idx]);
+            }
+        }
     }
+    matched
 }
@@ -0,0 +1,138 @@
+use crate::config::Config;
+use crate::execution::ExecutionEngine;
+use crate::risk::RiskManager;
+use crate::safe_mode::{SafeModeReason, SafeModeScope};
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// How long `invalidate_orders` waits for the `incrementNonce` receipt.
+const INVALIDATE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// What a kill did.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KillReport {
+    pub cancelled: usize,
+    pub cancel_error: Option<String>,
+    pub flattened: bool,
+    pub unsold: Decimal, // Shares flattening could not sell
+}
+
+/// Stops trading without stopping the process: halts with `kill_switch`, pulls
+/// every open order and, if asked, sells off inventory. Market data keeps
+/// streaming so the bot can still be watched; `resume kill_switch` restarts it.
+///
+/// Triggered by SIGUSR1, by `KILL_SWITCH_FILE` appearing, or by `kill` on the
+/// control port.
+pub struct KillSwitch {
+    risk_manager: RiskManager,
+    execution_engine: Arc<ExecutionEngine>,
+    sentinel: Option<PathBuf>,
+    flatten: bool,
+}
+
+impl KillSwitch {
+    pub fn new(config: &Config, risk_manager: RiskManager, execution_engine: Arc<ExecutionEngine>) -> Self {
+        Self {
+            risk_manager,
+            execution_engine,
+            sentinel: config.kill_switch_file.as_ref().map(PathBuf::from),
+            flatten: config.kill_switch_flatten,
+        }
+    }
+
+    /// Kills trading. `flatten` also sells inventory even if `KILL_SWITCH_FLATTEN` is off.
+    pub async fn trigger(&self, source: &str, flatten: bool) -> KillReport {
+        error!("KILL SWITCH triggered by {}", source);
+        self.risk_manager.enter_safe_mode(SafeModeReason::KillSwitch, SafeModeScope::Global, &format!("Kill switch via {}", source));
+
+        let mut report = KillReport::default();
+        match self.execution_engine.cancel_all().await {
+            Ok(resp) => report.cancelled = resp.canceled.len(),
+            Err(e) => {
+                error!("Kill switch cancel-all failed: {}", e);
+                report.cancel_error = Some(e.to_string());
+            }
+        }
+
+        if flatten || self.flatten {
+            report.flattened = true;
+            report.unsold = self.execution_engine.flatten().await;
+            if report.unsold > Decimal::ZERO {
+                error!("Kill switch left {} shares unsold", report.unsold);
+            }
+        }
+        info!("Kill switch done: {:?}", report);
+        report
+    }
+
+    /// Voids every order we have signed by bumping the exchange nonce on-chain.
+    /// Costs gas and waits for the transaction, so only an operator asks for it.
+    pub async fn invalidate_orders(&self) -> anyhow::Result<u64> {
+        warn!("Invalidating every signed order on-chain");
+        tokio::time::timeout(INVALIDATE_TIMEOUT, self.execution_engine.invalidate_all_orders())
+            .await
+            .map_err(|_| anyhow::anyhow!("incrementNonce not mined within {}s", INVALIDATE_TIMEOUT.as_secs()))?
+    }
+
+    /// Watches for SIGUSR1 and the sentinel file. The file kills once when it
+    /// appears, including at startup; remove it before resuming.
+    pub async fn run(&self) {
+        let mut signals = Signals::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut sentinel_seen = false;
+        loop {
+            tokio::select! {
+                _ = signals.recv() => {
+                    self.trigger("SIGUSR1", false).await;
+                }
+                _ = interval.tick() => {
+                    let Some(path) = &self.sentinel else { continue };
+                    let present = path.exists();
+                    if present && !sentinel_seen {
+                        self.trigger(&format!("sentinel file {}", path.display()), false).await;
+                    }
+                    sentinel_seen = present;
+                }
+            }
+        }
+    }
+}
+
+/// SIGUSR1 where the platform has it; never fires elsewhere.
+struct Signals {
+    #[cfg(unix)]
+    usr1: Option<tokio::signal::unix::Signal>,
+}
+
+impl Signals {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let usr1 = signal(SignalKind::user_defined1())
+                .map_err(|e| warn!("SIGUSR1 kill switch unavailable: {}", e))
+                .ok();
+            Self { usr1 }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        {
+            let fired = match self.usr1.as_mut() {
+                Some(usr1) => usr1.recv().await.is_some(),
+                None => false,
+            };
+            if fired {
+                return;
+            }
+            self.usr1 = None;
+        }
+        std::future::pending::<()>().await
+    }
+}
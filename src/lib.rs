@@ -0,0 +1,23 @@
+pub mod auth;
+pub mod breakers;
+pub mod backtest;
+pub mod capture;
+pub mod clock;
+pub mod config;
+pub mod contracts;
+pub mod control;
+pub mod kill_switch;
+pub mod nonce;
+pub mod orders;
+pub mod paper;
+pub mod positions;
+pub mod safe_mode;
+pub mod transport;
+pub mod types;
+pub mod market;
+pub mod execution;
+pub mod risk;
+pub mod strategy;
+pub mod sweep;
+pub mod telemetry;
+pub mod user_stream;
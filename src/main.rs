@@ -1,17 +1,14 @@
-mod config;
-mod types;
-mod market;
-mod execution;
-mod risk;
-mod strategy;
-
-use config::Config;
-use risk::RiskManager;
-use market::MarketMonitor;
-use execution::ExecutionEngine;
-use strategy::StrategyEngine;
+use polymarket_arb_bot::breakers::{BreakerLimits, CircuitBreakers};
+use polymarket_arb_bot::config::Config;
+use polymarket_arb_bot::control::ControlServer;
+use polymarket_arb_bot::kill_switch::KillSwitch;
+use polymarket_arb_bot::risk::{ExposureLimits, RiskManager};
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::execution::ExecutionEngine;
+use polymarket_arb_bot::strategy::StrategyEngine;
+use polymarket_arb_bot::user_stream::UserStream;
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber::FmtSubscriber;
 
 #[tokio::main]
@@ -26,41 +23,178 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Polymarket Binary Arbitrage Bot...");
 
     // 2. Load Config
-    let config = match Config::from_env() {
+    let mut config = match Config::from_env() {
         Ok(c) => c,
         Err(e) => {
             error!("Failed to load config: {}", e);
             return Ok(());
         }
     };
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--paper") {
+        config.paper_trading = true;
+    }
+    if let Some(i) = args.iter().position(|a| a == "--replay") {
+        match args.get(i + 1) {
+            Some(dir) => config.replay_dir = Some(dir.clone()),
+            None => {
+                error!("--replay needs a capture directory");
+                return Ok(());
+            }
+        }
+    }
+    // Replayed books are not the live exchange: never send real orders against them
+    if config.replay_dir.is_some() {
+        config.paper_trading = true;
+    }
     info!("Config loaded. Max Daily Loss: {}%", config.max_daily_loss_pct * rust_decimal::Decimal::from(100));
+    if config.paper_trading {
+        warn!("PAPER TRADING: orders are simulated against the cached order books.");
+    }
+    if let Some(dir) = &config.replay_dir {
+        warn!("REPLAY: market data comes from the capture in {}", dir);
+    }
 
     // 3. Initialize Components
-    // Mock initial balance of 1000 USDC
-    let initial_balance = rust_decimal::Decimal::from(1000);
-    let risk_manager = RiskManager::new(
-        initial_balance, 
+    let market_monitor = Arc::new(match config.replay_dir {
+        Some(_) => MarketMonitor::for_replay(config.clone()),
+        None => MarketMonitor::new(config.clone()),
+    });
+
+    // Live runs replace this with the exchange balance below
+    let mut risk_manager = RiskManager::new(
+        config.paper_balance,
         config.max_daily_loss_pct, 
         config.max_trade_capital_pct
-    );
-
-    let market_monitor = Arc::new(MarketMonitor::new(config.clone()));
-    let execution_engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone()));
+    )
+    .with_clock(market_monitor.clock())
+    .with_day_offset(config.risk_day_offset)
+    .with_exposure_limits(ExposureLimits::from_config(&config))
+    .with_auto_recover(config.safe_mode_auto_recover.clone());
+    // Only live state is kept: a paper breaker must not halt the next live run
+    if !config.paper_trading {
+        risk_manager = risk_manager
+            .with_audit_log(&config.audit_log_file)
+            .with_persistence(&config.risk_state_file)?;
+    }
+    let execution_engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), market_monitor.clone()));
+    let user_stream = UserStream::new(config.clone(), execution_engine.clone(), risk_manager.clone());
     let strategy_engine = StrategyEngine::new(market_monitor.clone(), execution_engine.clone(), config.clone());
 
     // 4. Start Background Tasks
+
+    // Sign new orders with the on-chain exchange nonce if an RPC is available
+    if !config.paper_trading {
+        if let Err(e) = execution_engine.sync_exchange_nonce().await {
+            warn!("Exchange nonce not synced, signing with nonce 0: {}", e);
+        }
+    }
+
+    // Size risk limits from the real collateral, and keep it reconciled
+    if !config.paper_trading {
+        match execution_engine.fetch_collateral().await {
+            Ok(collateral) => risk_manager.set_starting_balance(collateral),
+            Err(e) => {
+                error!("Failed to fetch USDC balance: {}", e);
+                return Ok(());
+            }
+        }
+        let engine_clone = execution_engine.clone();
+        tokio::spawn(async move {
+            engine_clone.run_balance_sync().await;
+        });
+    } else {
+        info!("Paper balance: {} USDC", config.paper_balance);
+    }
     
-    // Start market discovery (Mocked)
-    market_monitor.start_market_discovery().await;
-    
-    // Start WebSocket Loop
-    let monitor_clone = market_monitor.clone();
+    let engine_clone = execution_engine.clone();
+    tokio::spawn(async move {
+        engine_clone.run_mark_to_market().await;
+    });
+
+    // Start market discovery and the WebSocket loop, unless replaying a capture
+    if config.replay_dir.is_none() {
+        market_monitor.start_market_discovery().await;
+
+        let monitor_clone = market_monitor.clone();
+        tokio::spawn(async move {
+            monitor_clone.run_ws_loop().await;
+        });
+    }
+
+    // Start User Channel (our own orders & trades). Paper orders never reach the exchange.
+    if !config.paper_trading {
+        tokio::spawn(async move {
+            user_stream.run_ws_loop().await;
+        });
+    }
+
+    let breakers = CircuitBreakers::new(market_monitor.telemetry(), risk_manager.clone(), BreakerLimits::from_config(&config));
     tokio::spawn(async move {
-        monitor_clone.run_ws_loop().await;
+        breakers.run().await;
     });
 
-    // 5. Run Strategy Loop (Event Driven)
-    strategy_engine.run().await;
+    // SIGUSR1, the sentinel file or `kill` on the control port stop trading; data keeps flowing
+    let kill_switch = Arc::new(KillSwitch::new(&config, risk_manager.clone(), execution_engine.clone()));
+    let kill_switch_clone = kill_switch.clone();
+    tokio::spawn(async move {
+        kill_switch_clone.run().await;
+    });
+
+    if let Some(addr) = &config.control_addr {
+        let server = ControlServer::bind(addr, risk_manager.clone()).await?.with_kill_switch(kill_switch);
+        tokio::spawn(server.run());
+    }
+
+    // Pull resting orders whenever the circuit breaker trips
+    let mut safe_mode_rx = risk_manager.subscribe_safe_mode();
+    let engine_clone = execution_engine.clone();
+    tokio::spawn(async move {
+        while safe_mode_rx.changed().await.is_ok() {
+            if *safe_mode_rx.borrow_and_update() {
+                warn!("SAFE MODE entered. Cancelling all open orders.");
+                if let Err(e) = engine_clone.cancel_all().await {
+                    error!("Cancel-all on safe mode failed: {}", e);
+                }
+            }
+        }
+    });
+
+    // 5. Run Strategy Loop (Event Driven) until shutdown or the end of the replay
+    let replay_done = async {
+        match &config.replay_dir {
+            Some(dir) => {
+                if let Err(e) = market_monitor.replay(std::path::Path::new(dir), config.replay_speed).await {
+                    error!("Replay failed: {}", e);
+                }
+            }
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        biased; // The strategy must subscribe to updates before the replay emits any
+        _ = strategy_engine.run() => {}
+        _ = replay_done => {
+            execution_engine.mark_to_market();
+            info!("Replay complete. Daily PnL: {}, equity: {}", risk_manager.daily_pnl(), risk_manager.equity());
+        }
+        _ = tokio::signal::ctrl_c() => {
+            info!("Shutdown requested.");
+        }
+    }
+
+    info!("Cancelling all open orders before exit...");
+    if let Err(e) = execution_engine.cancel_all().await {
+        error!("Cancel-all on shutdown failed: {}", e);
+    }
+
+    for position in risk_manager.positions().positions().iter().filter(|p| !p.quantity.is_zero()) {
+        info!("Holding {} of {} in {} @ {}", position.quantity, position.token_id, position.market_id, position.avg_cost.round_dp(4));
+    }
+    for trade in execution_engine.orders().unsettled() {
+        warn!("Trade {} still {} on-chain; its fills can yet fail", trade.trade_id, trade.status);
+    }
+    risk_manager.flush();
 
     Ok(())
 }
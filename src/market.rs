@@ -1,13 +1,17 @@
-use crate::types::{Market, Token, OrderBook, Level, WsMessage, WsSubscribeMsg, WsLevel, MarketResponse};
+use crate::types::{Market, MarkPrice, OrderBook, Level, WsMessage, WsSubscribeMsg, MarketResponse};
 use crate::config::Config;
+use crate::capture::{self, CaptureWriter};
+use crate::clock::{Clock, ReplayClock, SystemClock};
+use crate::telemetry::Telemetry;
+use crate::transport::{HttpRequest, HttpTransport, ReqwestTransport, TungsteniteConnector, WsConnector};
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
 use rust_decimal::Decimal;
-use chrono::{Utc, TimeZone};
-use reqwest::Client;
+use chrono::{DateTime, Utc, TimeZone};
 use tracing::{info, error, warn, debug};
 use tokio::sync::broadcast;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::tungstenite::protocol::Message;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use futures_util::{StreamExt, SinkExt};
 use std::time::Duration;
@@ -16,48 +20,113 @@ pub struct MarketMonitor {
     active_markets: Arc<RwLock<HashMap<String, Market>>>,
     token_to_market: Arc<RwLock<HashMap<String, String>>>, // token_id -> market_id
     order_books: Arc<RwLock<HashMap<String, OrderBook>>>, // token_id -> OrderBook
-    client: Client,
     config: Config,
+    clock: Arc<dyn Clock>,
+    replay_clock: Option<Arc<ReplayClock>>, // Set for monitors built with `for_replay`
+    http: Arc<dyn HttpTransport>,
+    ws: Arc<dyn WsConnector>,
     pub update_tx: broadcast::Sender<String>, // Broadcasts market_id on update
+    capture: Option<Mutex<CaptureWriter>>,
+    telemetry: Arc<Telemetry>,
 }
 
 impl MarketMonitor {
     pub fn new(config: Config) -> Self {
+        Self::with_transport(config, Arc::new(SystemClock), Arc::new(ReqwestTransport::new()), Arc::new(TungsteniteConnector))
+    }
+
+    /// A monitor whose clock follows capture time during `replay`.
+    pub fn for_replay(config: Config) -> Self {
+        let clock = Arc::new(ReplayClock::new());
+        let mut monitor = Self::with_transport(config, clock.clone(), Arc::new(ReqwestTransport::new()), Arc::new(TungsteniteConnector));
+        monitor.replay_clock = Some(clock);
+        monitor
+    }
+
+    pub fn with_transport(config: Config, clock: Arc<dyn Clock>, http: Arc<dyn HttpTransport>, ws: Arc<dyn WsConnector>) -> Self {
         let (update_tx, _) = broadcast::channel(100);
+
+        let capture = config.capture_dir.as_ref().and_then(|dir| {
+            match CaptureWriter::new(dir, config.capture_rotate_secs) {
+                Ok(writer) => Some(Mutex::new(writer)),
+                Err(e) => {
+                    error!("Capture disabled: {}", e);
+                    None
+                }
+            }
+        });
         
         Self {
+            telemetry: Arc::new(Telemetry::new(clock.clone())),
             active_markets: Arc::new(RwLock::new(HashMap::new())),
             token_to_market: Arc::new(RwLock::new(HashMap::new())),
             order_books: Arc::new(RwLock::new(HashMap::new())),
-            client: Client::new(),
             config,
+            clock,
+            replay_clock: None,
+            http,
+            ws,
             update_tx,
+            capture,
         }
     }
 
+    /// Feed and order health, shared with the engines built on this monitor.
+    pub fn telemetry(&self) -> Arc<Telemetry> {
+        self.telemetry.clone()
+    }
+
+    /// The clock shared with the engines built on this monitor.
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    pub fn http(&self) -> Arc<dyn HttpTransport> {
+        self.http.clone()
+    }
+
+    pub fn ws(&self) -> Arc<dyn WsConnector> {
+        self.ws.clone()
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
     pub async fn start_market_discovery(&self) {
         info!("Starting market discovery via REST API...");
         
         let url = format!("{}/markets?active=true&limit=100", self.config.http_url);
         
-        match self.client.get(&url).send().await {
-            Ok(resp) => {
-                if let Ok(market_response) = resp.json::<MarketResponse>().await {
-                    let mut count = 0;
-                    for market in market_response.data {
-                        if self.is_valid_crypto_market(&market) {
-                            self.add_market(market);
-                            count += 1;
-                        }
-                    }
-                    info!("Discovered {} valid crypto markets.", count);
-                } else {
-                    error!("Failed to parse markets response");
-                }
-            },
+        let body = match self.http.send(HttpRequest::get(url)).await {
+            Ok(resp) => resp.body,
             Err(e) => {
                 error!("Failed to fetch markets: {}", e);
+                return;
+            }
+        };
+
+        if let Some(capture) = &self.capture {
+            if let Err(e) = capture.lock().unwrap().write_markets(self.clock.now(), &body) {
+                error!("Failed to capture markets response: {}", e);
+            }
+        }
+        self.load_markets(&body);
+    }
+
+    /// Adds the valid crypto markets from a `/markets` response body.
+    pub fn load_markets(&self, body: &str) {
+        if let Ok(market_response) = serde_json::from_str::<MarketResponse>(body) {
+            let mut count = 0;
+            for market in market_response.data {
+                if self.is_valid_crypto_market(&market) {
+                    self.add_market(market);
+                    count += 1;
+                }
             }
+            info!("Discovered {} valid crypto markets.", count);
+        } else {
+            error!("Failed to parse markets response");
         }
     }
 
@@ -111,12 +180,11 @@ impl MarketMonitor {
             let headers = request.headers_mut();
             headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36".parse().unwrap());
 
-            match connect_async(request).await {
-                Ok((ws_stream, _)) => {
+            match self.ws.connect(request).await {
+                Ok((mut write, mut read)) => {
                     info!("WebSocket Connected");
                     backoff = 1; // Reset backoff on success
-                    
-                    let (mut write, mut read) = ws_stream.split();
+                    self.telemetry.ws_connected();
 
                     // 1. Subscribe
                     let tokens: Vec<String> = {
@@ -154,7 +222,10 @@ impl MarketMonitor {
                                 match msg {
                                     Some(Ok(message)) => {
                                         match message {
-                                            Message::Text(text) => self.handle_message(&text),
+                                            Message::Text(text) => {
+                                                self.record(&text);
+                                                self.handle_message(&text);
+                                            },
                                             Message::Ping(payload) => {
                                                 // Respond to server Ping with Pong
                                                 if let Err(e) = write.send(Message::Pong(payload)).await {
@@ -191,6 +262,7 @@ impl MarketMonitor {
                     error!("WS Connection Failed: {}", e);
                 }
             }
+            self.telemetry.ws_disconnected();
             
             // Exponential Backoff
             let wait_secs = std::cmp::min(backoff, 60);
@@ -200,6 +272,54 @@ impl MarketMonitor {
         }
     }
 
+    fn record(&self, text: &str) {
+        if let Some(capture) = &self.capture {
+            if let Err(e) = capture.lock().unwrap().write_frame(self.clock.now(), text) {
+                error!("Failed to capture WS frame: {}", e);
+            }
+        }
+    }
+
+    /// Feeds a capture made with `CAPTURE_DIR` back through the message handler
+    /// in place of the live WebSocket.
+    ///
+    /// Frames are paced by their receive timestamps divided by `speed`; a speed
+    /// of 0 replays as fast as possible. Monitors built with `for_replay` also
+    /// move their clock along capture time. Returns once the capture is exhausted.
+    pub async fn replay(&self, dir: &Path, speed: f64) -> anyhow::Result<()> {
+        for body in capture::read_markets(dir)? {
+            self.load_markets(&body);
+        }
+
+        info!("Replaying {} at {}x", dir.display(), speed);
+        self.telemetry.ws_connected(); // The capture stands in for the feed
+        let mut prev_ts: Option<i64> = None;
+        let mut count = 0u64;
+        for frame in capture::read_frames(dir)? {
+            let received_at = Utc.timestamp_millis_opt(frame.ts).single().unwrap_or_else(|| self.clock.now());
+            if let Some(clock) = self.replay_clock.as_ref().filter(|_| prev_ts.is_none()) {
+                clock.start(received_at, speed);
+            }
+            if let Some(prev) = prev_ts {
+                let gap_ms = (frame.ts - prev).max(0) as f64;
+                if speed > 0.0 && gap_ms > 0.0 {
+                    tokio::time::sleep(Duration::from_secs_f64(gap_ms / speed / 1000.0)).await;
+                } else {
+                    // Let the strategy keep up with the broadcast channel
+                    tokio::task::yield_now().await;
+                }
+            }
+            prev_ts = Some(frame.ts);
+            if let Some(clock) = &self.replay_clock {
+                clock.on_frame(received_at);
+            }
+            self.handle_message(&frame.frame);
+            count += 1;
+        }
+        info!("Replay finished: {} frames", count);
+        Ok(())
+    }
+
     fn handle_message(&self, text: &str) {
         if text == "[]" { return; } // Ignore heartbeat
         match serde_json::from_str::<WsMessage>(text) {
@@ -214,7 +334,7 @@ impl MarketMonitor {
                     Utc.timestamp_millis_opt(ts).single()
                 } else {
                     Utc.timestamp_opt(ts, 0).single()
-                }.unwrap_or(Utc::now());
+                }.unwrap_or_else(|| self.clock.now());
 
                 let token_id = update.asset_id.clone();
                 
@@ -286,6 +406,11 @@ impl MarketMonitor {
         None
     }
     
+    pub fn get_market_tags(&self, market_id: &str) -> Vec<String> {
+        let markets = self.active_markets.read().unwrap();
+        markets.get(market_id).and_then(|m| m.tags.clone()).unwrap_or_default()
+    }
+
     pub fn get_market_state_clone(&self, market_id: &str) -> Option<crate::types::MarketState> {
          let markets = self.active_markets.read().unwrap();
          markets.get(market_id).map(|m| m.state.clone())
@@ -296,7 +421,24 @@ impl MarketMonitor {
         if let Some(market) = markets.get_mut(market_id) {
             market.state.is_normalized = false;
             market.state.consecutive_normalized_updates = 0;
-            market.state.last_trade_time = Some(Utc::now());
+            market.state.last_trade_time = Some(self.now());
+        }
+    }
+
+    pub fn get_book(&self, token_id: &str) -> Option<OrderBook> {
+        self.order_books.read().unwrap().get(token_id).cloned()
+    }
+
+    /// Price to value a held token at: the best bid, or the bid/ask midpoint for
+    /// `MarkPrice::Mid` when both sides are quoted.
+    pub fn get_mark_price(&self, token_id: &str, mark: MarkPrice) -> Option<Decimal> {
+        let books = self.order_books.read().unwrap();
+        let book = books.get(token_id)?;
+        let best_bid = book.bids.iter().map(|l| l.price).max();
+        let best_ask = book.asks.iter().map(|l| l.price).min();
+        match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) if mark == MarkPrice::Mid => Some((bid + ask) / Decimal::TWO),
+            (bid, _) => bid,
         }
     }
 
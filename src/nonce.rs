@@ -0,0 +1,72 @@
+use ethers::core::rand::{thread_rng, Rng};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::warn;
+
+// Keep salts within 2^53 so they survive a round trip through JSON numbers.
+const SALT_MASK: u64 = (1 << 53) - 1;
+/// Salts remembered for the collision check. Random 53-bit salts practically never
+/// repeat, and orders signed long ago no longer matter.
+const RECENT_SALTS: usize = 4096;
+
+/// Hands out per-order salts and tracks the exchange-level nonce.
+///
+/// Salts only need to be unique so that two orders with otherwise identical
+/// fields (e.g. both legs of an arb signed in the same millisecond) never
+/// hash to the same value. The exchange nonce is shared by every order we
+/// sign; incrementing it on-chain invalidates all of them at once.
+#[derive(Debug)]
+pub struct NonceManager {
+    exchange_nonce: AtomicU64,
+    recent_salts: Mutex<RecentSalts>,
+}
+
+#[derive(Debug, Default)]
+struct RecentSalts {
+    set: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl NonceManager {
+    pub fn new(exchange_nonce: u64) -> Self {
+        Self {
+            exchange_nonce: AtomicU64::new(exchange_nonce),
+            recent_salts: Mutex::new(RecentSalts::default()),
+        }
+    }
+
+    /// Returns a random salt that none of the last `RECENT_SALTS` salts issued by
+    /// this manager used.
+    pub fn next_salt(&self) -> u64 {
+        let mut recent = self.recent_salts.lock().unwrap();
+        let mut rng = thread_rng();
+        loop {
+            let salt = rng.gen::<u64>() & SALT_MASK;
+            if salt != 0 && recent.set.insert(salt) {
+                recent.order.push_back(salt);
+                if recent.order.len() > RECENT_SALTS {
+                    let oldest = recent.order.pop_front().unwrap();
+                    recent.set.remove(&oldest);
+                }
+                return salt;
+            }
+            warn!("Salt collision on {}, regenerating", salt);
+        }
+    }
+
+    /// The exchange nonce new orders must be signed with.
+    pub fn current_nonce(&self) -> u64 {
+        self.exchange_nonce.load(Ordering::SeqCst)
+    }
+
+    /// Overwrites the local nonce with the value read from the exchange contract.
+    pub fn set_nonce(&self, nonce: u64) {
+        self.exchange_nonce.store(nonce, Ordering::SeqCst);
+    }
+
+    /// Advances the local nonce after an on-chain `incrementNonce`, returning the new value.
+    pub fn bump_nonce(&self) -> u64 {
+        self.exchange_nonce.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
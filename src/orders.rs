@@ -0,0 +1,493 @@
+use crate::clock::{Clock, SystemClock};
+use crate::types::{FillReport, OrderRequest, OrderType, Side};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// How long a finished order stays in the registry for late events and lookups.
+pub const RETENTION_SECS: i64 = 3600;
+
+/// Lifecycle of an order we sign.
+///
+/// ```text
+/// Created -> Signed -> Submitted -> Live -> PartiallyFilled -> Filled
+///    |         |          |           |            |
+///    +---------+----------+-----------+------------+--> Rejected / Cancelled / Expired
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderState {
+    Created,
+    Signed,
+    Submitted,
+    Live,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+    Expired,
+}
+
+impl OrderState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Filled | Self::Cancelled | Self::Rejected | Self::Expired)
+    }
+
+    /// Orders that can still match on the book (or are about to reach it).
+    pub fn is_open(&self) -> bool {
+        matches!(self, Self::Submitted | Self::Live | Self::PartiallyFilled)
+    }
+
+    pub fn can_transition_to(&self, next: OrderState) -> bool {
+        use OrderState::*;
+        matches!(
+            (self, next),
+            (Created, Signed | Rejected)
+                | (Signed, Submitted | Rejected)
+                | (Submitted, Live | PartiallyFilled | Filled | Cancelled | Rejected | Expired)
+                | (Live, PartiallyFilled | Filled | Cancelled | Expired)
+                | (PartiallyFilled, PartiallyFilled | Filled | Cancelled | Expired)
+        )
+    }
+
+    /// Maps an order status string from the CLOB (REST or POST response) to a state.
+    pub fn from_clob_status(status: &str) -> Option<Self> {
+        match status.to_ascii_uppercase().as_str() {
+            "LIVE" | "DELAYED" => Some(Self::Live),
+            "MATCHED" => Some(Self::Filled),
+            // FOK/FAK orders that could not match are killed
+            "UNMATCHED" | "CANCELED" | "CANCELLED" | "CANCELED_MARKET_RESOLVED" => Some(Self::Cancelled),
+            "INVALID" => Some(Self::Rejected),
+            "EXPIRED" => Some(Self::Expired),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OrderState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// On-chain settlement of a trade. A CLOB match is only final once it is mined
+/// on Polygon and confirmed; until then it can still fail.
+///
+/// ```text
+/// Matched -> Mined -> Confirmed
+///    |         |          ^
+///    +---------+--> Retrying
+///    |         |      |
+///    +---------+------+--> Failed
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettlementStatus {
+    Matched,
+    Mined,
+    Confirmed,
+    Retrying,
+    Failed,
+}
+
+impl SettlementStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Confirmed | Self::Failed)
+    }
+
+    pub fn can_transition_to(&self, next: SettlementStatus) -> bool {
+        use SettlementStatus::*;
+        matches!(
+            (self, next),
+            (Matched, Mined | Confirmed | Retrying | Failed)
+                | (Mined, Confirmed | Retrying | Failed)
+                | (Retrying, Mined | Confirmed | Failed)
+        )
+    }
+
+    /// Maps a trade status string from the CLOB (REST or user channel).
+    pub fn from_clob_status(status: &str) -> Option<Self> {
+        match status.to_ascii_uppercase().as_str() {
+            "MATCHED" => Some(Self::Matched),
+            "MINED" => Some(Self::Mined),
+            "CONFIRMED" => Some(Self::Confirmed),
+            "RETRYING" => Some(Self::Retrying),
+            "FAILED" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SettlementStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Our share of one trade: the order it filled and by how much.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettlementLeg {
+    pub order_id: String,
+    pub size: Decimal,
+    pub price: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct TradeSettlement {
+    pub trade_id: String,
+    pub status: SettlementStatus,
+    pub legs: Vec<SettlementLeg>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What unwinding a failed trade takes back from an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Reversal {
+    pub shares: Decimal, // Fill already booked to risk
+    pub pnl: Decimal, // Profit booked on the strength of those shares
+}
+
+/// Profit booked across the fills of one or more orders, e.g. both legs of an arb.
+struct PnlBooking {
+    quantity: Decimal,
+    pnl_per_share: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedOrder {
+    pub order_id: String, // EIP-712 order hash, which the CLOB uses as the order id
+    pub market_id: String,
+    pub token_id: String,
+    pub side: Side,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub order_type: OrderType,
+    pub state: OrderState,
+    pub size_matched: Decimal,
+    pub matched_notional: Option<Decimal>, // From the POST response amounts, when given
+    pub traded_size: Decimal, // From trade events, which carry exact prices
+    pub traded_notional: Decimal,
+    pub trade_ids: HashSet<String>,
+    pub failed_size: Decimal, // Matched in trades that later failed on-chain
+    pub booked_size: Decimal, // Fill booked to risk and not since reversed
+    pub fill_verified: bool, // Fill checked and booked once; later fills are booked as they arrive
+    pub pnl_booking: Option<Uuid>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TrackedOrder {
+    pub fn filled_size(&self) -> Decimal {
+        (self.size_matched.max(self.traded_size) - self.failed_size).max(Decimal::ZERO)
+    }
+
+    pub fn avg_price(&self) -> Decimal {
+        if self.traded_size > Decimal::ZERO {
+            self.traded_notional / self.traded_size
+        } else if let Some(notional) = self.matched_notional.filter(|_| self.size_matched > Decimal::ZERO) {
+            notional / self.size_matched
+        } else if self.filled_size() > Decimal::ZERO {
+            self.price
+        } else {
+            Decimal::ZERO
+        }
+    }
+
+    pub fn fill_report(&self) -> FillReport {
+        FillReport {
+            order_id: Some(self.order_id.clone()),
+            status: self.state.to_string(),
+            filled_size: self.filled_size(),
+            avg_price: self.avg_price(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// In-memory record of every order this process has signed, keyed by order id,
+/// and of the settlement of every trade they took part in.
+pub struct OrderRegistry {
+    orders: RwLock<HashMap<String, TrackedOrder>>,
+    settlements: RwLock<HashMap<String, TradeSettlement>>,
+    bookings: RwLock<HashMap<Uuid, PnlBooking>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for OrderRegistry {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+}
+
+impl OrderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            orders: RwLock::new(HashMap::new()),
+            settlements: RwLock::new(HashMap::new()),
+            bookings: RwLock::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    pub fn create(&self, order_id: &str, req: &OrderRequest) {
+        let now = self.clock.now();
+        let order = TrackedOrder {
+            order_id: order_id.to_string(),
+            market_id: req.market_id.clone(),
+            token_id: req.token_id.clone(),
+            side: req.side.clone(),
+            price: req.price,
+            size: req.size,
+            order_type: req.order_type,
+            state: OrderState::Created,
+            size_matched: Decimal::ZERO,
+            matched_notional: None,
+            traded_size: Decimal::ZERO,
+            traded_notional: Decimal::ZERO,
+            trade_ids: HashSet::new(),
+            failed_size: Decimal::ZERO,
+            booked_size: Decimal::ZERO,
+            fill_verified: false,
+            pnl_booking: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.orders.write().unwrap().insert(order_id.to_string(), order);
+    }
+
+    /// Moves an order to `next`, refusing illegal transitions.
+    pub fn transition(&self, order_id: &str, next: OrderState) -> Result<(), String> {
+        let mut orders = self.orders.write().unwrap();
+        let order = orders.get_mut(order_id).ok_or_else(|| format!("Unknown order {}", order_id))?;
+        Self::apply_transition(order, next, self.clock.now())
+    }
+
+    fn apply_transition(order: &mut TrackedOrder, next: OrderState, now: DateTime<Utc>) -> Result<(), String> {
+        if !order.state.can_transition_to(next) {
+            return Err(format!("Illegal transition {} -> {} for order {}", order.state, next, order.order_id));
+        }
+        order.state = next;
+        order.updated_at = now;
+        Ok(())
+    }
+
+    pub fn reject(&self, order_id: &str, error: String) -> Result<(), String> {
+        self.transition(order_id, OrderState::Rejected)?;
+        if let Some(order) = self.orders.write().unwrap().get_mut(order_id) {
+            order.error = Some(error);
+        }
+        Ok(())
+    }
+
+    /// Records a cumulative matched size (and its notional, if known),
+    /// moving the order to PartiallyFilled or Filled.
+    pub fn record_matched(&self, order_id: &str, size_matched: Decimal, notional: Option<Decimal>) -> Result<(), String> {
+        let mut orders = self.orders.write().unwrap();
+        let order = orders.get_mut(order_id).ok_or_else(|| format!("Unknown order {}", order_id))?;
+        if size_matched <= order.size_matched {
+            return Ok(());
+        }
+        order.size_matched = size_matched;
+        if notional.is_some() {
+            order.matched_notional = notional;
+        }
+        Self::advance_fill_state(order, self.clock.now());
+        Ok(())
+    }
+
+    /// Adds a trade execution, ignoring trades already counted at an earlier status.
+    pub fn record_trade(&self, order_id: &str, trade_id: &str, size: Decimal, price: Decimal) -> Result<(), String> {
+        let mut orders = self.orders.write().unwrap();
+        let order = orders.get_mut(order_id).ok_or_else(|| format!("Unknown order {}", order_id))?;
+        if order.trade_ids.insert(trade_id.to_string()) {
+            let now = self.clock.now();
+            order.traded_size += size;
+            order.traded_notional += size * price;
+            order.size_matched = order.size_matched.max(order.traded_size);
+            Self::advance_fill_state(order, now);
+
+            self.settlements.write().unwrap()
+                .entry(trade_id.to_string())
+                .or_insert_with(|| TradeSettlement {
+                    trade_id: trade_id.to_string(),
+                    status: SettlementStatus::Matched,
+                    legs: Vec::new(),
+                    updated_at: now,
+                })
+                .legs.push(SettlementLeg { order_id: order_id.to_string(), size, price });
+        }
+        Ok(())
+    }
+
+    /// Moves a trade to `next`, refusing illegal transitions. Repeats of the
+    /// current status are ignored. Returns the trade if this update failed it,
+    /// after taking its legs off their orders' filled size.
+    pub fn settle(&self, trade_id: &str, next: SettlementStatus) -> Result<Option<TradeSettlement>, String> {
+        let mut settlements = self.settlements.write().unwrap();
+        let settlement = settlements.get_mut(trade_id).ok_or_else(|| format!("Unknown trade {}", trade_id))?;
+        if settlement.status == next {
+            return Ok(None);
+        }
+        if !settlement.status.can_transition_to(next) {
+            return Err(format!("Illegal settlement {} -> {} for trade {}", settlement.status, next, trade_id));
+        }
+        settlement.status = next;
+        settlement.updated_at = self.clock.now();
+        if next != SettlementStatus::Failed {
+            return Ok(None);
+        }
+        // record_trade takes orders then settlements; never hold both the other way round
+        let failed = settlement.clone();
+        drop(settlements);
+
+        let mut orders = self.orders.write().unwrap();
+        for leg in &failed.legs {
+            if let Some(order) = orders.get_mut(&leg.order_id) {
+                order.failed_size += leg.size;
+                order.updated_at = failed.updated_at;
+            }
+        }
+        Ok(Some(failed))
+    }
+
+    pub fn settlement(&self, trade_id: &str) -> Option<TradeSettlement> {
+        self.settlements.read().unwrap().get(trade_id).cloned()
+    }
+
+    /// Trades that are neither confirmed nor failed yet.
+    pub fn unsettled(&self) -> Vec<TradeSettlement> {
+        self.settlements.read().unwrap()
+            .values()
+            .filter(|s| !s.status.is_final())
+            .cloned()
+            .collect()
+    }
+
+    /// Notes that `size` of an order's fill has been booked to risk, once its
+    /// fill has been verified. Fill reported after that goes through `take_unbooked`.
+    pub fn record_booked_fill(&self, order_id: &str, size: Decimal) -> Result<(), String> {
+        let mut orders = self.orders.write().unwrap();
+        let order = orders.get_mut(order_id).ok_or_else(|| format!("Unknown order {}", order_id))?;
+        order.booked_size += size;
+        order.fill_verified = true;
+        Ok(())
+    }
+
+    /// Claims the fill a verified order has gained beyond what was booked, marking
+    /// it booked. Returns the order and the shares to book, if there are any.
+    pub fn take_unbooked(&self, order_id: &str) -> Option<(TrackedOrder, Decimal)> {
+        let mut orders = self.orders.write().unwrap();
+        let order = orders.get_mut(order_id).filter(|o| o.fill_verified)?;
+        let unbooked = order.filled_size() - order.booked_size;
+        if unbooked <= Decimal::ZERO {
+            return None;
+        }
+        order.booked_size += unbooked;
+        Some((order.clone(), unbooked))
+    }
+
+    /// Records `pnl_per_share` booked on `quantity` shares filled by `order_ids`.
+    /// A failure on any of them takes back the profit on the shares it failed.
+    pub fn book_pnl(&self, booking: Uuid, order_ids: &[&str], quantity: Decimal, pnl_per_share: Decimal) {
+        let mut orders = self.orders.write().unwrap();
+        for order_id in order_ids {
+            if let Some(order) = orders.get_mut(*order_id) {
+                order.pnl_booking = Some(booking);
+            }
+        }
+        self.bookings.write().unwrap().insert(booking, PnlBooking { quantity, pnl_per_share });
+    }
+
+    /// Takes `size` failed shares back off an order's booked fill and PnL.
+    /// Shares that failed before they were booked have nothing to reverse.
+    pub fn reverse(&self, order_id: &str, size: Decimal) -> Reversal {
+        let mut orders = self.orders.write().unwrap();
+        let Some(order) = orders.get_mut(order_id) else { return Reversal::default() };
+        let shares = size.min(order.booked_size);
+        order.booked_size -= shares;
+
+        let mut bookings = self.bookings.write().unwrap();
+        let pnl = match order.pnl_booking.and_then(|id| bookings.get_mut(&id)) {
+            Some(booking) => {
+                let unbooked = shares.min(booking.quantity);
+                booking.quantity -= unbooked;
+                unbooked * booking.pnl_per_share
+            }
+            None => Decimal::ZERO,
+        };
+        Reversal { shares, pnl }
+    }
+
+    fn advance_fill_state(order: &mut TrackedOrder, now: DateTime<Utc>) {
+        order.updated_at = now;
+        let next = if order.size_matched >= order.size { OrderState::Filled } else { OrderState::PartiallyFilled };
+        if order.state != next && order.state.can_transition_to(next) {
+            order.state = next;
+        }
+    }
+
+    /// Marks open GTD orders whose expiration has passed as Expired, returning their ids.
+    pub fn expire_due(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut orders = self.orders.write().unwrap();
+        let mut expired = Vec::new();
+        for order in orders.values_mut() {
+            if let OrderType::Gtd { expiration } = order.order_type {
+                if expiration <= now && order.state.is_open() && Self::apply_transition(order, OrderState::Expired, now).is_ok() {
+                    expired.push(order.order_id.clone());
+                }
+            }
+        }
+        expired
+    }
+
+    /// Forgets orders that finished, and trades that confirmed or failed, more than
+    /// `RETENTION_SECS` ago. Orders with a trade still settling are kept, as are
+    /// the PnL bookings of the orders kept. Returns how many orders were dropped.
+    pub fn prune(&self, now: DateTime<Utc>) -> usize {
+        let cutoff = now - Duration::seconds(RETENTION_SECS);
+        let settling: HashSet<String> = {
+            let mut settlements = self.settlements.write().unwrap();
+            settlements.retain(|_, s| !s.status.is_final() || s.updated_at >= cutoff);
+            settlements.values().filter(|s| !s.status.is_final()).map(|s| s.trade_id.clone()).collect()
+        };
+
+        let mut orders = self.orders.write().unwrap();
+        let before = orders.len();
+        orders.retain(|_, o| !o.state.is_terminal() || o.updated_at >= cutoff || o.trade_ids.iter().any(|t| settling.contains(t)));
+        let booked: HashSet<Uuid> = orders.values().filter_map(|o| o.pnl_booking).collect();
+        self.bookings.write().unwrap().retain(|id, _| booked.contains(id));
+        before - orders.len()
+    }
+
+    pub fn get(&self, order_id: &str) -> Option<TrackedOrder> {
+        self.orders.read().unwrap().get(order_id).cloned()
+    }
+
+    /// Every order placed in a market, oldest first.
+    pub fn market_orders(&self, market_id: &str) -> Vec<TrackedOrder> {
+        let mut orders: Vec<TrackedOrder> = self.orders.read().unwrap()
+            .values()
+            .filter(|o| o.market_id == market_id)
+            .cloned()
+            .collect();
+        orders.sort_by_key(|o| o.created_at);
+        orders
+    }
+
+    /// Orders that may still fill, optionally restricted to one market.
+    pub fn open_orders(&self, market_id: Option<&str>) -> Vec<TrackedOrder> {
+        self.orders.read().unwrap()
+            .values()
+            .filter(|o| o.state.is_open())
+            .filter(|o| market_id.is_none_or(|m| o.market_id == m))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+use crate::market::MarketMonitor;
+use crate::orders::RETENTION_SECS;
+use crate::types::{CancelResponse, CancelScope, Level, OpenOrder, OrderRequest, OrderResponse, OrderType, Side};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::info;
+
+/// Simulated exchange for `--paper` runs.
+///
+/// Orders are matched against the cached `OrderBook` depth from `MarketMonitor`
+/// after a configurable latency, with fills worsened by a slippage in basis points
+/// (never beyond the order's limit). Only `queue_share` of each level's displayed
+/// size is assumed to reach us ahead of competing takers. The book itself is not
+/// consumed, and resting GTC/GTD remainders are not matched against later updates.
+pub struct PaperMatcher {
+    market_monitor: Arc<MarketMonitor>,
+    latency: Duration,
+    slippage_bps: Decimal,
+    queue_share: Decimal,
+    orders: Mutex<HashMap<String, PaperOrder>>,
+}
+
+#[derive(Debug)]
+struct PaperOrder {
+    market_id: String,
+    token_id: String,
+    status: &'static str, // "LIVE", "MATCHED", "CANCELED"
+    size_matched: Decimal,
+    updated_at: DateTime<Utc>,
+}
+
+impl PaperMatcher {
+    pub fn new(market_monitor: Arc<MarketMonitor>, latency_ms: u64, slippage_bps: Decimal, queue_share: Decimal) -> Self {
+        Self {
+            market_monitor,
+            latency: Duration::from_millis(latency_ms),
+            slippage_bps,
+            queue_share,
+            orders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Matches an order against the current book, answering like `POST /order` would.
+    pub async fn submit(&self, order_id: &str, req: &OrderRequest) -> OrderResponse {
+        sleep(self.latency).await;
+
+        let Some(book) = self.market_monitor.get_book(&req.token_id) else {
+            return OrderResponse {
+                success: false,
+                error_msg: format!("paper: no book for {}", req.token_id),
+                order_id: order_id.to_string(),
+                ..Default::default()
+            };
+        };
+
+        // Levels we can trade against, best first
+        let mut levels: Vec<Level> = match req.side {
+            Side::Buy => book.asks.into_iter().filter(|l| l.price <= req.price).collect(),
+            Side::Sell => book.bids.into_iter().filter(|l| l.price >= req.price).collect(),
+        };
+        match req.side {
+            Side::Buy => levels.sort_by_key(|l| l.price),
+            Side::Sell => levels.sort_by_key(|l| std::cmp::Reverse(l.price)),
+        }
+
+        for level in levels.iter_mut() {
+            level.size *= self.queue_share;
+        }
+
+        let available: Decimal = levels.iter().map(|l| l.size).sum();
+        let fillable = available.min(req.size);
+        let filled = if req.order_type == OrderType::Fok && fillable < req.size {
+            Decimal::ZERO
+        } else {
+            fillable
+        };
+
+        let mut remaining = filled;
+        let mut notional = Decimal::ZERO;
+        for level in &levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let qty = level.size.min(remaining);
+            notional += qty * self.slipped_price(level.price, req);
+            remaining -= qty;
+        }
+
+        let resting = !req.order_type.is_immediate() && filled < req.size;
+        let status = if filled > Decimal::ZERO {
+            "matched"
+        } else if resting {
+            "live"
+        } else {
+            "unmatched"
+        };
+
+        self.orders.lock().unwrap().insert(order_id.to_string(), PaperOrder {
+            market_id: req.market_id.clone(),
+            token_id: req.token_id.clone(),
+            status: if resting { "LIVE" } else if filled > Decimal::ZERO { "MATCHED" } else { "CANCELED" },
+            size_matched: filled,
+            updated_at: self.market_monitor.clock().now(),
+        });
+        info!("PAPER {:?} {} x {} @ {} ({}): filled {} for {}", req.side, req.token_id, req.size, req.price, req.order_type, filled, notional);
+
+        // BUY: we make USDC and take shares. SELL: the reverse.
+        let (making, taking) = match req.side {
+            Side::Buy => (notional, filled),
+            Side::Sell => (filled, notional),
+        };
+        OrderResponse {
+            success: true,
+            error_msg: String::new(),
+            order_id: order_id.to_string(),
+            status: status.to_string(),
+            making_amount: if filled > Decimal::ZERO { making.to_string() } else { String::new() },
+            taking_amount: if filled > Decimal::ZERO { taking.to_string() } else { String::new() },
+        }
+    }
+
+    fn slipped_price(&self, price: Decimal, req: &OrderRequest) -> Decimal {
+        let slip = price * self.slippage_bps / Decimal::from(10_000);
+        match req.side {
+            Side::Buy => (price + slip).min(req.price),
+            Side::Sell => (price - slip).max(req.price),
+        }
+    }
+
+    /// Answers like `GET /data/order/{id}`.
+    pub fn order(&self, order_id: &str) -> Option<OpenOrder> {
+        let orders = self.orders.lock().unwrap();
+        orders.get(order_id).map(|o| OpenOrder {
+            status: o.status.to_string(),
+            size_matched: o.size_matched.to_string(),
+            associate_trades: vec![],
+        })
+    }
+
+    /// Cancels resting paper orders, answering like the `DELETE` endpoints.
+    pub fn cancel(&self, scope: &CancelScope) -> CancelResponse {
+        let now = self.market_monitor.clock().now();
+        let mut orders = self.orders.lock().unwrap();
+        let mut resp = CancelResponse::default();
+        for (order_id, order) in orders.iter_mut() {
+            let in_scope = match scope {
+                CancelScope::Orders(ids) => ids.contains(order_id),
+                CancelScope::Market { market_id, asset_id } => {
+                    order.market_id == *market_id && asset_id.as_ref().is_none_or(|a| order.token_id == *a)
+                }
+                CancelScope::All => true,
+            };
+            if !in_scope {
+                continue;
+            }
+            if order.status == "LIVE" {
+                order.status = "CANCELED";
+                order.updated_at = now;
+                resp.canceled.push(order_id.clone());
+            } else if let CancelScope::Orders(_) = scope {
+                resp.not_canceled.insert(order_id.clone(), "order is not live".to_string());
+            }
+        }
+        resp
+    }
+
+    /// Forgets orders that matched or were cancelled more than `RETENTION_SECS`
+    /// ago. Returns how many were dropped.
+    pub fn prune(&self, now: DateTime<Utc>) -> usize {
+        let cutoff = now - chrono::Duration::seconds(RETENTION_SECS);
+        let mut orders = self.orders.lock().unwrap();
+        let before = orders.len();
+        orders.retain(|_, o| o.status == "LIVE" || o.updated_at >= cutoff);
+        before - orders.len()
+    }
+}
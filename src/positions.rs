@@ -0,0 +1,195 @@
+use crate::types::Side;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+/// Shares we hold of one outcome token.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Position {
+    pub token_id: String,
+    pub market_id: String,
+    pub quantity: Decimal,
+    pub avg_cost: Decimal, // USDC per share of what we still hold
+    pub realized_pnl: Decimal, // From sells, against avg_cost
+    pub mark_price: Option<Decimal>, // Last mark-to-market price
+}
+
+impl Position {
+    pub fn cost_basis(&self) -> Decimal {
+        self.quantity * self.avg_cost
+    }
+}
+
+/// Holdings in one binary market.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MarketExposure {
+    pub yes_quantity: Decimal,
+    pub no_quantity: Decimal,
+    pub net: Decimal, // YES - NO: what is exposed to the outcome
+    pub sets: Decimal, // Complete YES+NO pairs
+    pub locked_value: Decimal, // Each set redeems for 1 USDC whatever the outcome
+    pub cost_basis: Decimal,
+}
+
+/// Positions valued at current prices.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Valuation {
+    pub market_value: Decimal,
+    pub cost_basis: Decimal,
+    pub unrealized_pnl: Decimal, // market_value - cost_basis
+    pub realized_pnl: Decimal,
+}
+
+impl Valuation {
+    /// PnL of everything traded since the ledger started.
+    pub fn total_pnl(&self) -> Decimal {
+        self.realized_pnl + self.unrealized_pnl
+    }
+}
+
+/// Per-token inventory built from confirmed fills.
+#[derive(Debug, Default)]
+pub struct PositionLedger {
+    positions: RwLock<HashMap<String, Position>>, // token_id -> position
+}
+
+impl PositionLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply_fill(&self, market_id: &str, token_id: &str, side: &Side, quantity: Decimal, price: Decimal) {
+        if quantity <= Decimal::ZERO {
+            return;
+        }
+        let mut positions = self.positions.write().unwrap();
+        let position = positions.entry(token_id.to_string()).or_insert_with(|| Position {
+            token_id: token_id.to_string(),
+            market_id: market_id.to_string(),
+            ..Default::default()
+        });
+
+        match side {
+            Side::Buy => {
+                let total = position.quantity + quantity;
+                position.avg_cost = (position.cost_basis() + quantity * price) / total;
+                position.quantity = total;
+            }
+            Side::Sell => {
+                // We never short: selling more than we hold means a fill we didn't book
+                let closed = quantity.min(position.quantity);
+                if quantity > position.quantity {
+                    warn!("Sold {} of {} but ledger holds {}", quantity, token_id, position.quantity);
+                }
+                position.realized_pnl += (price - position.avg_cost) * closed;
+                position.quantity -= closed;
+                if position.quantity.is_zero() {
+                    position.avg_cost = Decimal::ZERO;
+                }
+            }
+        }
+        info!("Position {}: {} @ {} (realized {})", token_id, position.quantity, position.avg_cost.round_dp(4), position.realized_pnl);
+    }
+
+    /// Undoes a fill booked by `apply_fill`, e.g. a trade that failed on-chain.
+    pub fn reverse_fill(&self, token_id: &str, side: &Side, quantity: Decimal, price: Decimal) {
+        let mut positions = self.positions.write().unwrap();
+        let Some(position) = positions.get_mut(token_id).filter(|_| quantity > Decimal::ZERO) else { return };
+
+        match side {
+            Side::Buy => {
+                let reversed = quantity.min(position.quantity);
+                let remaining = position.quantity - reversed;
+                position.avg_cost = if remaining.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    (position.cost_basis() - reversed * price) / remaining
+                };
+                position.quantity = remaining;
+            }
+            Side::Sell => {
+                // The shares come back at the cost they were sold against
+                if position.quantity.is_zero() {
+                    position.avg_cost = price;
+                }
+                position.realized_pnl -= (price - position.avg_cost) * quantity;
+                position.quantity += quantity;
+            }
+        }
+        info!("Reversed {:?} {} of {}: now {} @ {}", side, quantity, token_id, position.quantity, position.avg_cost.round_dp(4));
+    }
+
+    pub fn position(&self, token_id: &str) -> Option<Position> {
+        self.positions.read().unwrap().get(token_id).cloned()
+    }
+
+    /// Every token we hold or have traded, sorted by market.
+    pub fn positions(&self) -> Vec<Position> {
+        let mut positions: Vec<Position> = self.positions.read().unwrap().values().cloned().collect();
+        positions.sort_by(|a, b| (&a.market_id, &a.token_id).cmp(&(&b.market_id, &b.token_id)));
+        positions
+    }
+
+    pub fn market_exposure(&self, yes_token: &str, no_token: &str) -> MarketExposure {
+        let positions = self.positions.read().unwrap();
+        let (yes, no) = (positions.get(yes_token), positions.get(no_token));
+        let yes_quantity = yes.map(|p| p.quantity).unwrap_or_default();
+        let no_quantity = no.map(|p| p.quantity).unwrap_or_default();
+        let sets = yes_quantity.min(no_quantity);
+        MarketExposure {
+            yes_quantity,
+            no_quantity,
+            net: yes_quantity - no_quantity,
+            sets,
+            locked_value: sets,
+            cost_basis: yes.map(Position::cost_basis).unwrap_or_default() + no.map(Position::cost_basis).unwrap_or_default(),
+        }
+    }
+
+    /// Values every position at `price_of(token_id)`, remembering each mark.
+    ///
+    /// Complete YES+NO sets are worth 1 USDC whatever the books say; only the
+    /// unpaired remainder is marked. Tokens without a price keep their last mark,
+    /// or their average cost if never marked.
+    pub fn mark(&self, price_of: impl Fn(&str) -> Option<Decimal>) -> Valuation {
+        let mut positions = self.positions.write().unwrap();
+        let mut markets: HashMap<String, Vec<Decimal>> = HashMap::new();
+        for position in positions.values() {
+            markets.entry(position.market_id.clone()).or_default().push(position.quantity);
+        }
+        let sets = |market_id: &str| match markets[market_id][..] {
+            [a, b] => a.min(b),
+            _ => Decimal::ZERO,
+        };
+
+        let mut valuation = Valuation::default();
+        for position in positions.values_mut() {
+            if let Some(price) = price_of(&position.token_id) {
+                position.mark_price = Some(price);
+            }
+            let price = position.mark_price.unwrap_or(position.avg_cost);
+            let paired = sets(&position.market_id);
+            // Half a set's 1 USDC to each leg
+            valuation.market_value += paired / Decimal::TWO + (position.quantity - paired) * price;
+            valuation.cost_basis += position.cost_basis();
+            valuation.realized_pnl += position.realized_pnl;
+        }
+        valuation.unrealized_pnl = valuation.market_value - valuation.cost_basis;
+        valuation
+    }
+
+    /// Cost of the shares held in each market we have a position in.
+    pub fn deployed_by_market(&self) -> HashMap<String, Decimal> {
+        let mut deployed: HashMap<String, Decimal> = HashMap::new();
+        for position in self.positions.read().unwrap().values().filter(|p| p.quantity > Decimal::ZERO) {
+            *deployed.entry(position.market_id.clone()).or_default() += position.cost_basis();
+        }
+        deployed
+    }
+
+    pub fn realized_pnl(&self) -> Decimal {
+        self.positions.read().unwrap().values().map(|p| p.realized_pnl).sum()
+    }
+}
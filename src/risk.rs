@@ -1,41 +1,350 @@
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::orders::SettlementStatus;
+use crate::positions::{PositionLedger, Valuation};
+use crate::safe_mode::{AuditEntry, SafeModeAction, SafeModeEntry, SafeModeReason, SafeModeScope};
+use crate::types::{CollateralBalance, Side};
+use anyhow::Context;
+use chrono::{FixedOffset, NaiveDate};
 use rust_decimal::Decimal;
-use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use tokio::sync::watch;
 use tracing::{error, info, warn};
+use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+/// Closed trading days kept in the persisted history.
+const HISTORY_DAYS: usize = 90;
+
+#[derive(Clone)]
 pub struct RiskManager {
     state: Arc<Mutex<RiskState>>,
     max_daily_loss_pct: Decimal,
     max_trade_capital_pct: Decimal,
+    safe_mode_tx: Arc<watch::Sender<bool>>,
+    positions: Arc<PositionLedger>,
+    clock: Arc<dyn Clock>,
+    day_offset: FixedOffset, // Trading days start at local midnight in this offset
+    writer: Option<Arc<StateWriter>>,
+    audit_path: Option<PathBuf>,
+    auto_recover: Vec<SafeModeReason>, // Reasons that clear themselves once their condition does
+    limits: ExposureLimits,
+}
+
+/// Caps on capital held in positions, as fractions of the current balance.
+/// `None` leaves a cap off.
+#[derive(Debug, Clone, Default)]
+pub struct ExposureLimits {
+    pub max_market_pct: Option<Decimal>,
+    pub max_tag_pct: Option<Decimal>,
+    pub max_total_pct: Option<Decimal>,
+    pub max_open_positions: Option<usize>,
+}
+
+impl ExposureLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_market_pct: Some(config.max_market_capital_pct),
+            max_tag_pct: Some(config.max_tag_capital_pct),
+            max_total_pct: Some(config.max_total_capital_pct),
+            max_open_positions: Some(config.max_open_positions),
+        }
+    }
+}
+
+/// One closed trading day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyPnl {
+    pub day: NaiveDate,
+    pub pnl: Decimal,
+    pub equity_pnl: Option<Decimal>,
+    pub closing_balance: Decimal,
 }
 
-#[derive(Debug)]
+/// Everything but the transient fields survives a restart via `RISK_STATE_FILE`.
+#[derive(Debug, Serialize, Deserialize)]
 struct RiskState {
-    initial_balance: Decimal,
+    day: NaiveDate,
+    initial_balance: Decimal, // At the start of the day
     current_balance: Decimal,
     daily_pnl: Decimal,
-    safe_mode: bool,
+    equity_pnl: Option<Decimal>, // Today's marked PnL, once positions have been marked
+    halts: Vec<SafeModeEntry>, // Active safe modes
+    history: Vec<DailyPnl>,
+    #[serde(skip)]
+    cash: Option<Decimal>, // Expected USDC collateral, once synced with the exchange
+    #[serde(skip)]
+    allowance: Option<Decimal>,
+    #[serde(default)]
+    unhedged: HashMap<String, HashMap<String, Decimal>>, // market_id -> token_id -> shares
+    #[serde(skip)]
+    market_tags: HashMap<String, Vec<String>>, // Tags of every market we've traded
+    #[serde(skip)]
+    valuation: Option<Valuation>, // Latest mark-to-market of the ledger
+    #[serde(skip)]
+    equity_offset: Decimal, // Added to the ledger's lifetime PnL to get today's
+    #[serde(skip)]
+    restored: bool, // Today's state was loaded from disk
+    #[serde(skip)]
+    holds: HashMap<Uuid, CapitalHold>, // Capital reserved for trades in flight
+}
+
+/// Capital set aside for one trade until its orders fill or die.
+#[derive(Debug, Clone)]
+struct CapitalHold {
+    market_id: String,
+    amount: Decimal,
+}
+
+impl RiskState {
+    /// The worse of booked PnL and marked equity PnL: booked arb profit is only
+    /// theoretical until sets redeem, and marks can lag a fast move.
+    fn loss_pnl(&self) -> Decimal {
+        self.equity_pnl.map_or(self.daily_pnl, |e| self.daily_pnl.min(e))
+    }
+
+    fn reserved(&self) -> Decimal {
+        self.holds.values().map(|h| h.amount).sum()
+    }
+
+    fn global_halt(&self) -> Option<&SafeModeEntry> {
+        self.halts.iter().find(|h| h.scope == SafeModeScope::Global)
+    }
 }
 
 impl RiskManager {
     pub fn new(initial_balance: Decimal, max_daily_loss_pct: Decimal, max_trade_capital_pct: Decimal) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let day_offset = FixedOffset::east_opt(0).unwrap();
         Self {
             state: Arc::new(Mutex::new(RiskState {
+                day: clock.now().with_timezone(&day_offset).date_naive(),
                 initial_balance,
                 current_balance: initial_balance,
                 daily_pnl: Decimal::ZERO,
-                safe_mode: false,
+                equity_pnl: None,
+                halts: Vec::new(),
+                history: Vec::new(),
+                cash: None,
+                allowance: None,
+                unhedged: HashMap::new(),
+                market_tags: HashMap::new(),
+                valuation: None,
+                equity_offset: Decimal::ZERO,
+                restored: false,
+                holds: HashMap::new(),
             })),
             max_daily_loss_pct,
             max_trade_capital_pct,
+            safe_mode_tx: Arc::new(watch::channel(false).0),
+            positions: Arc::new(PositionLedger::new()),
+            clock,
+            day_offset,
+            writer: None,
+            audit_path: None,
+            auto_recover: Vec::new(),
+            limits: ExposureLimits::default(),
+        }
+    }
+
+    /// Appends every safe-mode transition to `path` as a JSON line.
+    pub fn with_audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_path = Some(path.into());
+        self
+    }
+
+    /// Lets halts for `reasons` clear without an operator once the condition that
+    /// tripped them has gone: the next day for `DailyLoss`, a clean reconcile for
+    /// `ReconciliationDrift`, fresh data for `StaleData`. Reasons not listed wait
+    /// for an operator; the default `SAFE_MODE_AUTO_RECOVER` leaves out `DailyLoss`.
+    pub fn with_auto_recover(mut self, reasons: Vec<SafeModeReason>) -> Self {
+        self.auto_recover = reasons;
+        self
+    }
+
+    pub fn with_exposure_limits(mut self, limits: ExposureLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Uses `clock` to tell when the trading day rolls over.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self.state.lock().unwrap().day = self.today();
+        self
+    }
+
+    /// Starts trading days at midnight in `offset` instead of UTC.
+    pub fn with_day_offset(mut self, offset: FixedOffset) -> Self {
+        self.day_offset = offset;
+        self.state.lock().unwrap().day = self.today();
+        self
+    }
+
+    /// Loads state saved by a previous run from `path`, if any, and saves every
+    /// change back to it. A tripped breaker therefore survives a restart, and so
+    /// does the day's PnL unless the day has rolled over since.
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let saved: RiskState = serde_json::from_slice(&fs::read(&path)?)
+                .with_context(|| format!("Invalid risk state in {}", path.display()))?;
+            let mut state = self.state.lock().unwrap();
+            *state = saved;
+            state.equity_offset = state.equity_pnl.unwrap_or_default(); // The ledger starts empty
+            state.restored = true;
+            info!("Risk state restored from {}: day {}, daily PnL {}", path.display(), state.day, state.daily_pnl);
+            if state.global_halt().is_some() {
+                self.safe_mode_tx.send_replace(true);
+            }
+            for halt in &state.halts {
+                error!("SAFE MODE restored from a previous run: {}", halt);
+            }
+            for (market_id, tokens) in &state.unhedged {
+                for (token_id, shares) in tokens {
+                    error!("UNHEDGED EXPOSURE restored from a previous run: {} shares of {} in market {}", shares, token_id, market_id);
+                }
+            }
+        }
+        self.writer = Some(Arc::new(StateWriter::spawn(path)));
+        drop(self.lock());
+        Ok(self)
+    }
+
+    /// Blocks until every state change so far is on disk.
+    pub fn flush(&self) {
+        if let Some(writer) = &self.writer {
+            writer.flush();
         }
     }
 
+    fn today(&self) -> NaiveDate {
+        self.clock.now().with_timezone(&self.day_offset).date_naive()
+    }
+
+    /// Locks the state, closing out the trading day first if it has ended.
+    fn lock(&self) -> MutexGuard<'_, RiskState> {
+        let mut state = self.state.lock().unwrap();
+        let today = self.today();
+        if today > state.day {
+            self.roll_day(&mut state, today);
+        }
+        state
+    }
+
+    fn roll_day(&self, state: &mut RiskState, today: NaiveDate) {
+        info!("Trading day {} closed: PnL {}, equity PnL {:?}, balance {}",
+            state.day, state.daily_pnl, state.equity_pnl, state.current_balance);
+        state.history.push(DailyPnl {
+            day: state.day,
+            pnl: state.daily_pnl,
+            equity_pnl: state.equity_pnl,
+            closing_balance: state.current_balance,
+        });
+        let excess = state.history.len().saturating_sub(HISTORY_DAYS);
+        state.history.drain(..excess);
+
+        state.day = today;
+        state.initial_balance = state.current_balance;
+        state.daily_pnl = Decimal::ZERO;
+        if let Some(equity_pnl) = state.equity_pnl.replace(Decimal::ZERO) {
+            state.equity_offset -= equity_pnl;
+        }
+        state.restored = false;
+        self.auto_release(state, SafeModeReason::DailyLoss, &SafeModeScope::Global);
+        for halt in &state.halts {
+            warn!("SAFE MODE stays active into {}: {}", today, halt);
+        }
+        self.save(state);
+    }
+
+    /// Queues the state for the writer thread if it changed since the last save.
+    fn save(&self, state: &RiskState) {
+        let Some(writer) = &self.writer else { return };
+        match serde_json::to_vec_pretty(state) {
+            Ok(bytes) => writer.queue(bytes),
+            Err(e) => error!("Failed to serialize risk state: {}", e),
+        }
+    }
+
+    fn audit(&self, action: SafeModeAction, halt: &SafeModeEntry, by: &str) {
+        let entry = AuditEntry {
+            at: self.clock.now(),
+            action,
+            reason: halt.reason,
+            scope: halt.scope.clone(),
+            detail: halt.detail.clone(),
+            by: by.to_string(),
+        };
+        warn!(target: "audit", "SAFE MODE {:?} by {}: {}", action, by, halt);
+        let Some(path) = &self.audit_path else { return };
+        let line = serde_json::to_string(&entry).map(|l| l + "\n");
+        let written = line.map_err(anyhow::Error::from).and_then(|line| {
+            OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())?;
+            Ok(())
+        });
+        if let Err(e) = written {
+            error!("Failed to write audit log {}: {}", path.display(), e);
+        }
+    }
+
+    /// Notifies whenever global safe mode is entered or cleared, so resting orders
+    /// can be pulled.
+    pub fn subscribe_safe_mode(&self) -> watch::Receiver<bool> {
+        self.safe_mode_tx.subscribe()
+    }
+
+    /// Adds a halt unless one for the same reason and scope is already active.
+    fn activate_safe_mode(&self, state: &mut RiskState, reason: SafeModeReason, scope: SafeModeScope, detail: String) {
+        if state.halts.iter().any(|h| h.reason == reason && h.scope == scope) {
+            return;
+        }
+        let halt = SafeModeEntry { reason, scope, detail, since: self.clock.now() };
+        error!("Entering SAFE MODE: {}", halt);
+        self.audit(SafeModeAction::Enter, &halt, if matches!(reason, SafeModeReason::Manual | SafeModeReason::KillSwitch) { "operator" } else { "risk" });
+        if halt.scope == SafeModeScope::Global {
+            self.safe_mode_tx.send_replace(true);
+        }
+        state.halts.push(halt);
+        self.save(state);
+    }
+
+    fn release_halts(&self, state: &mut RiskState, matches: impl Fn(&SafeModeEntry) -> bool, by: &str) -> Vec<SafeModeEntry> {
+        let (cleared, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut state.halts).into_iter().partition(|h| matches(h));
+        state.halts = kept;
+        for halt in &cleared {
+            info!("SAFE MODE cleared by {}: {}", by, halt);
+            self.audit(SafeModeAction::Recover, halt, by);
+        }
+        if !cleared.is_empty() {
+            if state.global_halt().is_none() {
+                self.safe_mode_tx.send_replace(false);
+            }
+            self.save(state);
+        }
+        cleared
+    }
+
+    fn auto_release(&self, state: &mut RiskState, reason: SafeModeReason, scope: &SafeModeScope) {
+        if self.auto_recover.contains(&reason) {
+            self.release_halts(state, |h| h.reason == reason && &h.scope == scope, "auto");
+        }
+    }
+
+    /// Read-only pre-trade check. Capital reserved by trades in flight counts as spent;
+    /// use `reserve` to check and hold in one step.
     pub fn check_trade_size(&self, required_amount: Decimal) -> bool {
-        let state = self.state.lock().unwrap();
-        
-        if state.safe_mode {
-            warn!("Risk Check Failed: SAFE MODE is active.");
+        let state = self.lock();
+        self.trade_size_ok(&state, required_amount)
+    }
+
+    fn trade_size_ok(&self, state: &RiskState, required_amount: Decimal) -> bool {
+        if let Some(halt) = state.global_halt() {
+            warn!("Risk Check Failed: SAFE MODE is active ({}).", halt.reason);
             return false;
         }
 
@@ -45,9 +354,20 @@ impl RiskManager {
             return false;
         }
 
+        // Only trade collateral we actually have, and the exchange may pull, that isn't already held
+        let reserved = state.reserved();
+        if let Some(cash) = state.cash.filter(|c| required_amount > *c - reserved) {
+            warn!("Risk Check Failed: Trade size {} exceeds USDC balance {} less {} reserved", required_amount, cash, reserved);
+            return false;
+        }
+        if let Some(allowance) = state.allowance.filter(|a| required_amount > *a - reserved) {
+            warn!("Risk Check Failed: Trade size {} exceeds exchange allowance {} less {} reserved", required_amount, allowance, reserved);
+            return false;
+        }
+
         // Check daily loss limit
         let loss_limit = state.initial_balance * self.max_daily_loss_pct;
-        if state.daily_pnl < -loss_limit {
+        if state.loss_pnl() < -loss_limit {
              warn!("Risk Check Failed: Daily loss limit reached.");
              return false;
         }
@@ -55,27 +375,379 @@ impl RiskManager {
         true
     }
 
+    /// Checks that putting `required_amount` more into `market_id` stays within the
+    /// per-market, per-tag, total and open-position caps, given what we already hold
+    /// or have reserved.
+    pub fn check_exposure(&self, market_id: &str, tags: &[String], required_amount: Decimal) -> bool {
+        let mut state = self.lock();
+        state.market_tags.insert(market_id.to_string(), tags.to_vec());
+        self.exposure_ok(&state, market_id, tags, required_amount)
+    }
+
+    fn exposure_ok(&self, state: &RiskState, market_id: &str, tags: &[String], required_amount: Decimal) -> bool {
+        let mut deployed = self.positions.deployed_by_market();
+        for hold in state.holds.values() {
+            *deployed.entry(hold.market_id.clone()).or_default() += hold.amount;
+        }
+        let limits = &self.limits;
+        let balance = state.current_balance;
+
+        if let Some(pct) = limits.max_market_pct {
+            let held = deployed.get(market_id).copied().unwrap_or_default();
+            if held + required_amount > balance * pct {
+                warn!("Risk Check Failed: {} held in {} plus {} exceeds market limit {}", held, market_id, required_amount, balance * pct);
+                return false;
+            }
+        }
+
+        if let Some(pct) = limits.max_tag_pct {
+            for tag in tags {
+                let held: Decimal = deployed.iter()
+                    .filter(|(m, _)| state.market_tags.get(*m).is_some_and(|t| t.contains(tag)))
+                    .map(|(_, d)| *d)
+                    .sum();
+                if held + required_amount > balance * pct {
+                    warn!("Risk Check Failed: {} held under tag {} plus {} exceeds tag limit {}", held, tag, required_amount, balance * pct);
+                    return false;
+                }
+            }
+        }
+
+        if let Some(pct) = limits.max_total_pct {
+            let held: Decimal = deployed.values().sum();
+            if held + required_amount > balance * pct {
+                warn!("Risk Check Failed: {} deployed plus {} exceeds total limit {}", held, required_amount, balance * pct);
+                return false;
+            }
+        }
+
+        if let Some(max) = limits.max_open_positions {
+            if !deployed.contains_key(market_id) && deployed.len() >= max {
+                warn!("Risk Check Failed: {} open positions, limit {}", deployed.len(), max);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Runs the trade-size and exposure checks and, if both pass, holds `amount`
+    /// for trade `id` in the same step, so concurrent trades cannot both spend
+    /// the same balance. The hold stays until `commit`ted by fills or `release`d.
+    pub fn reserve(&self, id: Uuid, market_id: &str, tags: &[String], amount: Decimal) -> bool {
+        let mut state = self.lock();
+        state.market_tags.insert(market_id.to_string(), tags.to_vec());
+        if !self.trade_size_ok(&state, amount) || !self.exposure_ok(&state, market_id, tags, amount) {
+            return false;
+        }
+        state.holds.insert(id, CapitalHold { market_id: market_id.to_string(), amount });
+        info!("Reserved {} for {} on {} ({} reserved in total)", amount, id, market_id, state.reserved());
+        true
+    }
+
+    /// Converts `amount` of a hold into spent capital once fills are booked with
+    /// `record_fill`, leaving the rest held.
+    pub fn commit(&self, id: Uuid, amount: Decimal) {
+        let mut state = self.lock();
+        if let Some(hold) = state.holds.get_mut(&id) {
+            hold.amount = (hold.amount - amount).max(Decimal::ZERO);
+        }
+    }
+
+    /// Drops whatever is left of a hold, after a cancel, a failure or a partial fill.
+    /// Returns the amount freed; releasing an unknown or spent hold frees nothing.
+    pub fn release(&self, id: Uuid) -> Decimal {
+        let released = self.lock().holds.remove(&id).map(|h| h.amount).unwrap_or_default();
+        if released > Decimal::ZERO {
+            info!("Released {} held for {}", released, id);
+        }
+        released
+    }
+
+    /// Capital held for trades in flight.
+    pub fn reserved(&self) -> Decimal {
+        self.lock().reserved()
+    }
+
+    /// USDC free for new trades: the synced balance, capped by the allowance, less
+    /// what is reserved. `None` until the balance has been synced with the exchange.
+    pub fn available_balance(&self) -> Option<Decimal> {
+        let state = self.lock();
+        let cash = match (state.cash, state.allowance) {
+            (Some(cash), Some(allowance)) => cash.min(allowance),
+            (cash, _) => cash?,
+        };
+        Some(cash - state.reserved())
+    }
+
     pub fn record_pnl(&self, pnl: Decimal) {
-        let mut state = self.state.lock().unwrap();
+        let mut state = self.lock();
         state.daily_pnl += pnl;
         state.current_balance += pnl;
         
         info!("PnL Updated: Daily PnL: {}, Balance: {}", state.daily_pnl, state.current_balance);
+        self.check_loss_limit(&mut state);
+        self.save(&state);
+    }
 
+    /// Stores a fresh mark-to-market, which the loss limit then also applies to.
+    pub fn record_valuation(&self, valuation: Valuation) {
+        let mut state = self.lock();
+        state.equity_pnl = Some(valuation.total_pnl() + state.equity_offset);
+        state.valuation = Some(valuation);
+        self.check_loss_limit(&mut state);
+        self.save(&state);
+    }
+
+    fn check_loss_limit(&self, state: &mut RiskState) {
         let loss_limit = state.initial_balance * self.max_daily_loss_pct;
-        if state.daily_pnl < -loss_limit {
-            error!("CRITICAL: Daily loss limit hit! Entering SAFE MODE.");
-            state.safe_mode = true;
+        if state.loss_pnl() < -loss_limit {
+            let detail = format!("Daily loss limit {} hit (booked {}, equity {:?})", loss_limit, state.daily_pnl, state.equity_pnl);
+            self.activate_safe_mode(state, SafeModeReason::DailyLoss, SafeModeScope::Global, detail);
+        }
+    }
+
+    /// Seeds balances from the exchange at startup, replacing the constructor's estimate.
+    /// A restored day keeps its starting balance, so the loss limit doesn't reset.
+    pub fn set_starting_balance(&self, collateral: CollateralBalance) {
+        let mut state = self.lock();
+        if !state.restored {
+            state.initial_balance = collateral.balance;
         }
+        state.current_balance = collateral.balance;
+        state.cash = Some(collateral.balance);
+        state.allowance = Some(collateral.allowance);
+        info!("Starting balance: {} USDC (allowance {}), day started at {}", collateral.balance, collateral.allowance, state.initial_balance);
+        self.save(&state);
+    }
+
+    /// Books a confirmed fill: moves the expected USDC and updates the position ledger.
+    pub fn record_fill(&self, market_id: &str, token_id: &str, side: &Side, quantity: Decimal, price: Decimal) {
+        let notional = quantity * price;
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(cash) = state.cash.as_mut() {
+                match side {
+                    Side::Buy => *cash -= notional,
+                    Side::Sell => *cash += notional,
+                }
+            }
+        }
+        self.positions.apply_fill(market_id, token_id, side, quantity, price);
+    }
+
+    /// Takes back a fill booked by `record_fill` whose trade failed on-chain.
+    pub fn reverse_fill(&self, token_id: &str, side: &Side, quantity: Decimal, price: Decimal) {
+        let notional = quantity * price;
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(cash) = state.cash.as_mut() {
+                match side {
+                    Side::Buy => *cash += notional,
+                    Side::Sell => *cash -= notional,
+                }
+            }
+        }
+        self.positions.reverse_fill(token_id, side, quantity, price);
+    }
+
+    /// Shares held per token, from confirmed fills.
+    pub fn positions(&self) -> &PositionLedger {
+        &self.positions
+    }
+
+    /// Compares a fresh exchange balance with the cash we expect from our own fills,
+    /// alerting when they differ by more than `tolerance`, then adopts the exchange's
+    /// figures. Returns the drift (observed - expected).
+    ///
+    /// Deposits, withdrawals, redemptions and fills we never saw all show up as drift,
+    /// which halts trading until reviewed (or the next clean reconcile, if auto-recovering).
+    pub fn reconcile_balance(&self, collateral: CollateralBalance, tolerance: Decimal) -> Decimal {
+        let mut state = self.lock();
+        let expected = state.cash.unwrap_or(collateral.balance);
+        let drift = collateral.balance - expected;
+        if drift.abs() > tolerance {
+            error!("BALANCE DRIFT: exchange reports {} USDC, expected {} (drift {})", collateral.balance, expected, drift);
+            let detail = format!("Exchange reports {} USDC, expected {}", collateral.balance, expected);
+            self.activate_safe_mode(&mut state, SafeModeReason::ReconciliationDrift, SafeModeScope::Global, detail);
+        } else {
+            info!("Balance reconciled: {} USDC (drift {})", collateral.balance, drift);
+            self.auto_release(&mut state, SafeModeReason::ReconciliationDrift, &SafeModeScope::Global);
+        }
+        state.cash = Some(collateral.balance);
+        if state.allowance != Some(collateral.allowance) {
+            info!("Exchange allowance now {}", collateral.allowance);
+        }
+        state.allowance = Some(collateral.allowance);
+        drift
+    }
+
+    /// Expected USDC collateral, if synced.
+    pub fn cash(&self) -> Option<Decimal> {
+        self.state.lock().unwrap().cash
+    }
+
+    /// Records shares left without an offsetting leg after execution/recovery.
+    pub fn report_exposure(&self, market_id: &str, token_id: &str, shares: Decimal) {
+        let mut state = self.state.lock().unwrap();
+        let market = state.unhedged.entry(market_id.to_string()).or_default();
+        *market.entry(token_id.to_string()).or_default() += shares;
+        warn!("UNHEDGED EXPOSURE: {} shares of {} in market {} (total {})", shares, token_id, market_id, market[token_id]);
+        self.save(&state);
     }
 
-    pub fn enter_safe_mode(&self) {
+    /// Takes `shares` of `token_id` sold or re-hedged off its reported exposure.
+    pub fn clear_exposure(&self, market_id: &str, token_id: &str, shares: Decimal) {
         let mut state = self.state.lock().unwrap();
-        state.safe_mode = true;
-        error!("Manual trigger: Entering SAFE MODE.");
+        let Some(market) = state.unhedged.get_mut(market_id) else { return };
+        let Some(held) = market.get_mut(token_id) else { return };
+        *held -= shares;
+        if *held <= Decimal::ZERO {
+            market.remove(token_id);
+            info!("Unhedged exposure on {} in market {} cleared", token_id, market_id);
+        }
+        if market.is_empty() {
+            state.unhedged.remove(market_id);
+        }
+        self.save(&state);
+    }
+
+    pub fn has_unhedged_exposure(&self, market_id: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state.unhedged.get(market_id).is_some_and(|m| m.values().any(|q| *q > Decimal::ZERO))
+    }
+
+    pub fn enter_safe_mode(&self, reason: SafeModeReason, scope: SafeModeScope, detail: &str) {
+        let mut state = self.lock();
+        self.activate_safe_mode(&mut state, reason, scope, detail.to_string());
+    }
+
+    /// Operator recovery: clears the active halts matching `reason` and `scope`
+    /// (`None` matches any) and returns them. A halt whose condition still holds,
+    /// such as a daily loss over the limit, trips again at its next check.
+    pub fn recover(&self, reason: Option<SafeModeReason>, scope: Option<&SafeModeScope>) -> Vec<SafeModeEntry> {
+        let mut state = self.lock();
+        self.release_halts(&mut state, |h| reason.is_none_or(|r| h.reason == r) && scope.is_none_or(|s| &h.scope == s), "operator")
+    }
+
+    /// Clears a halt for `reason` once its condition has gone, if that reason auto-recovers.
+    pub fn resolve(&self, reason: SafeModeReason, scope: &SafeModeScope) {
+        let mut state = self.lock();
+        self.auto_release(&mut state, reason, scope);
     }
     
+    /// Reacts to settlement status changes streamed from the user channel.
+    pub fn on_trade_status(&self, trade_id: &str, market_id: &str, status: SettlementStatus) {
+        match status {
+            SettlementStatus::Failed => {
+                // The trade itself is unwound by the ExecutionEngine; only its market stops
+                error!("Trade {} on {} FAILED on-chain. Halting the market.", trade_id, market_id);
+                let detail = format!("Trade {} on {} failed on-chain", trade_id, market_id);
+                self.enter_safe_mode(SafeModeReason::SettlementFailed, SafeModeScope::Market(market_id.to_string()), &detail);
+            }
+            SettlementStatus::Retrying => warn!("Trade {} on {} is retrying settlement.", trade_id, market_id),
+            _ => {}
+        }
+    }
+
+    pub fn valuation(&self) -> Option<Valuation> {
+        self.state.lock().unwrap().valuation.clone()
+    }
+
+    /// The day's starting balance plus marked PnL, or booked PnL until the first mark.
+    pub fn equity(&self) -> Decimal {
+        let state = self.lock();
+        state.initial_balance + state.equity_pnl.unwrap_or(state.daily_pnl)
+    }
+
+    pub fn daily_pnl(&self) -> Decimal {
+        self.lock().daily_pnl
+    }
+
+    /// Closed trading days, oldest first.
+    pub fn pnl_history(&self) -> Vec<DailyPnl> {
+        self.lock().history.clone()
+    }
+
+    /// Whether a global halt is active.
     pub fn is_safe_mode(&self) -> bool {
-        self.state.lock().unwrap().safe_mode
+        self.lock().global_halt().is_some()
     }
+
+    /// The active global halt, if any.
+    pub fn safe_mode(&self) -> Option<SafeModeEntry> {
+        self.lock().global_halt().cloned()
+    }
+
+    /// The first active halt blocking `market_id`, global or its own.
+    pub fn market_halt(&self, market_id: &str) -> Option<SafeModeEntry> {
+        self.lock().halts.iter().find(|h| h.scope.covers(market_id)).cloned()
+    }
+
+    pub fn halts(&self) -> Vec<SafeModeEntry> {
+        self.lock().halts.clone()
+    }
+}
+
+/// Persists risk state on its own thread, so the risk lock is never held
+/// across disk I/O. Only the newest snapshot matters; older queued ones are skipped.
+struct StateWriter {
+    tx: Mutex<mpsc::Sender<(u64, Vec<u8>)>>,
+    latest: Mutex<(u64, Vec<u8>)>, // Sequence number and bytes of the last snapshot queued
+    written: Arc<(Mutex<u64>, Condvar)>, // Sequence number of the last snapshot written
+}
+
+impl StateWriter {
+    fn spawn(path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel::<(u64, Vec<u8>)>();
+        let written = Arc::new((Mutex::new(0), Condvar::new()));
+        let done = written.clone();
+        std::thread::spawn(move || {
+            // Ends once the last RiskManager holding the sender is gone
+            while let Ok(mut next) = rx.recv() {
+                while let Ok(newer) = rx.try_recv() {
+                    next = newer;
+                }
+                let (seq, bytes) = next;
+                if let Err(e) = write_atomically(&path, &bytes) {
+                    error!("Failed to persist risk state to {}: {}", path.display(), e);
+                }
+                *done.0.lock().unwrap() = seq;
+                done.1.notify_all();
+            }
+        });
+        Self { tx: Mutex::new(tx), latest: Mutex::new((0, Vec::new())), written }
+    }
+
+    fn queue(&self, bytes: Vec<u8>) {
+        let mut latest = self.latest.lock().unwrap();
+        if latest.1 == bytes {
+            return;
+        }
+        latest.0 += 1;
+        latest.1 = bytes.clone();
+        // Sent under `latest` so snapshots reach the writer in order
+        let _ = self.tx.lock().unwrap().send((latest.0, bytes));
+    }
+
+    fn flush(&self) {
+        let target = self.latest.lock().unwrap().0;
+        let (written, cond) = &*self.written;
+        let _guard = cond.wait_while(written.lock().unwrap(), |seq| *seq < target).unwrap();
+    }
+}
+
+impl Drop for StateWriter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Writes to a temporary file and renames it over `path`, so a crash mid-write
+/// never leaves a truncated state file behind.
+fn write_atomically(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
 }
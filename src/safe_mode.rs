@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Why trading was halted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeModeReason {
+    DailyLoss,
+    PartialFill,
+    StaleData,
+    Manual,
+    ReconciliationDrift,
+    SettlementFailed,
+    OrderErrors,
+    OrderLatency,
+    PartialFillRate,
+    KillSwitch,
+}
+
+impl SafeModeReason {
+    pub const ALL: [SafeModeReason; 10] = [
+        Self::DailyLoss,
+        Self::PartialFill,
+        Self::StaleData,
+        Self::Manual,
+        Self::ReconciliationDrift,
+        Self::SettlementFailed,
+        Self::OrderErrors,
+        Self::OrderLatency,
+        Self::PartialFillRate,
+        Self::KillSwitch,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DailyLoss => "daily_loss",
+            Self::PartialFill => "partial_fill",
+            Self::StaleData => "stale_data",
+            Self::Manual => "manual",
+            Self::ReconciliationDrift => "reconciliation_drift",
+            Self::SettlementFailed => "settlement_failed",
+            Self::OrderErrors => "order_errors",
+            Self::OrderLatency => "order_latency",
+            Self::PartialFillRate => "partial_fill_rate",
+            Self::KillSwitch => "kill_switch",
+        }
+    }
+}
+
+impl fmt::Display for SafeModeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SafeModeReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Self::ALL.into_iter()
+            .find(|r| r.name() == s.trim().to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Unknown safe mode reason {}: expected one of {}",
+                s, Self::ALL.map(|r| r.name()).join(", ")))
+    }
+}
+
+/// What a halt blocks: every trade, or just one market.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeModeScope {
+    Global,
+    Market(String),
+}
+
+impl SafeModeScope {
+    pub fn covers(&self, market_id: &str) -> bool {
+        match self {
+            Self::Global => true,
+            Self::Market(m) => m == market_id,
+        }
+    }
+}
+
+impl fmt::Display for SafeModeScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => f.write_str("global"),
+            Self::Market(m) => write!(f, "market {}", m),
+        }
+    }
+}
+
+/// An active halt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SafeModeEntry {
+    pub reason: SafeModeReason,
+    pub scope: SafeModeScope,
+    pub detail: String,
+    pub since: DateTime<Utc>,
+}
+
+impl fmt::Display for SafeModeEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}) since {}: {}", self.reason, self.scope, self.since.to_rfc3339(), self.detail)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeModeAction {
+    Enter,
+    Recover,
+}
+
+/// One safe-mode transition, as written to the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub action: SafeModeAction,
+    pub reason: SafeModeReason,
+    pub scope: SafeModeScope,
+    pub detail: String,
+    pub by: String, // "risk", "auto" or "operator"
+}
@@ -1,11 +1,12 @@
 use crate::market::MarketMonitor;
 use crate::execution::ExecutionEngine;
 use crate::config::Config;
+use crate::clock::Clock;
+use crate::types::{ArbLeg, OrderType};
 use rust_decimal::Decimal;
 use std::sync::Arc;
-use tracing::{info, warn, error};
+use tracing::{info, warn, error, debug};
 use tokio::sync::broadcast;
-use chrono::Utc;
 
 const TAKER_FEE: Decimal = Decimal::ZERO; 
 
@@ -13,14 +14,22 @@ pub struct StrategyEngine {
     market_monitor: Arc<MarketMonitor>,
     execution_engine: Arc<ExecutionEngine>,
     config: Config,
+    clock: Arc<dyn Clock>,
 }
 
 impl StrategyEngine {
+    /// Shares the monitor's clock.
     pub fn new(market_monitor: Arc<MarketMonitor>, execution_engine: Arc<ExecutionEngine>, config: Config) -> Self {
+        let clock = market_monitor.clock();
+        Self::with_clock(market_monitor, execution_engine, config, clock)
+    }
+
+    pub fn with_clock(market_monitor: Arc<MarketMonitor>, execution_engine: Arc<ExecutionEngine>, config: Config, clock: Arc<dyn Clock>) -> Self {
         Self {
             market_monitor,
             execution_engine,
             config,
+            clock,
         }
     }
 
@@ -52,6 +61,20 @@ impl StrategyEngine {
             None => return,
         };
         
+        // Don't stack a new arb on top of orders that may still fill
+        let now = self.clock.now();
+        for order_id in self.execution_engine.orders().expire_due(now) {
+            info!("Order {} expired", order_id);
+        }
+        self.execution_engine.prune(now);
+        let open_orders = self.execution_engine.orders().open_orders(Some(market_id));
+        if let Some(order) = open_orders.first() {
+            debug!("Skipping {}: order {} ({:?} {} on {}, age {}s) is still {}",
+                market_id, order.order_id, order.side, order.size, order.token_id,
+                now.signed_duration_since(order.created_at).num_seconds(), order.state);
+            return;
+        }
+
         // 2. Check Liquidity (Fast Fail)
         // Order size currently static 10.0, ideally dynamic.
         let trade_size = Decimal::new(10, 0); 
@@ -70,7 +93,7 @@ impl StrategyEngine {
             
             // Check Cooldown
             if let Some(last_trade) = state.last_trade_time {
-                let now = Utc::now();
+                let now = self.clock.now();
                 let elapsed = now.signed_duration_since(last_trade).num_milliseconds();
                 if elapsed < self.config.trade_cooldown_ms {
                      return; // Still cooling down
@@ -94,12 +117,13 @@ impl StrategyEngine {
                         
                         info!("EXECUTING TRADE on {}: YES @ {}, NO @ {}", market_id, final_yes, final_no);
                         
+                        let yes_leg = ArbLeg { token_id: yes_token.clone(), price: final_yes, order_type: self.leg_order_type() };
+                        let no_leg = ArbLeg { token_id: no_token.clone(), price: final_no, order_type: self.leg_order_type() };
+
                         let status = self.execution_engine.execute_arb(
                             market_id,
-                            &yes_token,
-                            &no_token,
-                            final_yes,
-                            final_no,
+                            &yes_leg,
+                            &no_leg,
                             trade_size
                         ).await;
                         
@@ -121,6 +145,16 @@ impl StrategyEngine {
         }
     }
 
+    /// Order type for each arb leg, per `ARB_ORDER_TYPE`.
+    fn leg_order_type(&self) -> OrderType {
+        match self.config.arb_order_type {
+            OrderType::Gtd { .. } => OrderType::Gtd {
+                expiration: self.clock.now() + chrono::Duration::seconds(self.config.gtd_ttl_secs),
+            },
+            order_type => order_type,
+        }
+    }
+
     fn check_opportunity(&self, price_yes: Decimal, price_no: Decimal) -> bool {
         let fee_multiplier = Decimal::ONE + TAKER_FEE;
         let cost_yes = price_yes * fee_multiplier;
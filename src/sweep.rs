@@ -0,0 +1,237 @@
+use crate::backtest::{self, BacktestReport};
+use crate::config::Config;
+use anyhow::{Context, Result};
+use ethers::core::rand::rngs::StdRng;
+use ethers::core::rand::seq::index;
+use ethers::core::rand::{Rng, SeedableRng};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::{error, info};
+
+/// Strategy `Config` fields a sweep can vary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SweepParam {
+    MinEdge,
+    MinLiquidityMultiplier,
+    NormalizationThreshold,
+    NormalizationUpdates,
+    TradeCooldownMs,
+}
+
+impl SweepParam {
+    pub const ALL: [SweepParam; 5] = [
+        Self::MinEdge,
+        Self::MinLiquidityMultiplier,
+        Self::NormalizationThreshold,
+        Self::NormalizationUpdates,
+        Self::TradeCooldownMs,
+    ];
+
+    /// Field name, also used for the CLI flag (`--min-edge`) and result columns.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MinEdge => "min_edge",
+            Self::MinLiquidityMultiplier => "min_liquidity_multiplier",
+            Self::NormalizationThreshold => "normalization_threshold",
+            Self::NormalizationUpdates => "normalization_updates",
+            Self::TradeCooldownMs => "trade_cooldown_ms",
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config, value: Decimal) -> Result<()> {
+        let integer = || value.to_i64().filter(|_| value.fract().is_zero())
+            .with_context(|| format!("{} must be an integer, got {}", self.name(), value));
+        match self {
+            Self::MinEdge => config.min_edge = value,
+            Self::MinLiquidityMultiplier => config.min_liquidity_multiplier = value,
+            Self::NormalizationThreshold => config.normalization_threshold = value,
+            Self::NormalizationUpdates => config.normalization_updates = u32::try_from(integer()?)?,
+            Self::TradeCooldownMs => config.trade_cooldown_ms = integer()?,
+        }
+        Ok(())
+    }
+}
+
+/// Parses `0.02,0.03,0.05` or an inclusive range `0.02:0.10:0.01` (start:end:step).
+pub fn parse_values(spec: &str) -> Result<Vec<Decimal>> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    if let [start, end, step] = parts[..] {
+        let (start, end, step) = (Decimal::from_str(start)?, Decimal::from_str(end)?, Decimal::from_str(step)?);
+        if step <= Decimal::ZERO || end < start {
+            anyhow::bail!("Invalid range {}: need start <= end and step > 0", spec);
+        }
+        let mut values = Vec::new();
+        let mut value = start;
+        while value <= end {
+            values.push(value);
+            value += step;
+        }
+        return Ok(values);
+    }
+    spec.split(',')
+        .map(|v| Decimal::from_str(v.trim()).with_context(|| format!("Invalid value {} in {}", v, spec)))
+        .collect()
+}
+
+/// The parameter grid: each axis lists the values to try for one field.
+#[derive(Debug, Clone, Default)]
+pub struct SweepSpace {
+    pub axes: Vec<(SweepParam, Vec<Decimal>)>,
+}
+
+impl SweepSpace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn axis(mut self, param: SweepParam, values: Vec<Decimal>) -> Self {
+        self.axes.push((param, values));
+        self
+    }
+
+    /// Every combination of axis values (the full grid).
+    pub fn grid(&self) -> Vec<Vec<Decimal>> {
+        self.axes.iter().fold(vec![vec![]], |combos, (_, values)| {
+            combos.iter()
+                .flat_map(|combo| values.iter().map(move |v| {
+                    let mut next = combo.clone();
+                    next.push(*v);
+                    next
+                }))
+                .collect()
+        })
+    }
+
+    /// `samples` distinct grid points chosen at random (random search).
+    ///
+    /// Draws positions in the grid and decodes each into one value per axis, so
+    /// a large grid is never built.
+    pub fn sample(&self, samples: usize, seed: u64) -> Vec<Vec<Decimal>> {
+        if self.axes.iter().any(|(_, values)| values.is_empty()) {
+            return Vec::new();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let Some(size) = self.axes.iter().try_fold(1usize, |n, (_, values)| n.checked_mul(values.len())) else {
+            // More points than a usize can count: a repeat is all but impossible, but skip any
+            let mut seen = HashSet::new();
+            let mut points = Vec::with_capacity(samples);
+            while points.len() < samples {
+                let indices: Vec<usize> = self.axes.iter().map(|(_, values)| rng.gen_range(0..values.len())).collect();
+                if seen.insert(indices.clone()) {
+                    points.push(self.point(&indices));
+                }
+            }
+            return points;
+        };
+        index::sample(&mut rng, size, samples.min(size))
+            .into_iter()
+            .map(|mut i| {
+                // Last axis varies fastest, as in `grid`
+                let mut indices = vec![0; self.axes.len()];
+                for (slot, (_, values)) in indices.iter_mut().zip(&self.axes).rev() {
+                    *slot = i % values.len();
+                    i /= values.len();
+                }
+                self.point(&indices)
+            })
+            .collect()
+    }
+
+    fn point(&self, indices: &[usize]) -> Vec<Decimal> {
+        self.axes.iter().zip(indices).map(|((_, values), i)| values[*i]).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepResult {
+    pub rank: usize,
+    pub params: BTreeMap<String, Decimal>,
+    pub pnl: Decimal,
+    pub max_drawdown: Decimal,
+    pub attempts: usize,
+    pub filled: usize,
+    pub partial: usize,
+    pub hit_rate: Decimal,
+    pub realized_edge: Decimal,
+}
+
+impl SweepResult {
+    fn new(space: &SweepSpace, values: &[Decimal], report: &BacktestReport) -> Self {
+        Self {
+            rank: 0,
+            params: space.axes.iter().zip(values).map(|((p, _), v)| (p.name().to_string(), *v)).collect(),
+            pnl: report.pnl,
+            max_drawdown: report.max_drawdown,
+            attempts: report.attempts,
+            filled: report.filled,
+            partial: report.partial,
+            hit_rate: report.hit_rate,
+            realized_edge: report.realized_edge,
+        }
+    }
+}
+
+/// Backtests every point on `jobs` threads over the same capture and ranks
+/// the results by PnL, then by smaller drawdown.
+///
+/// Each point runs on its own runtime and simulated clock, so runs do not
+/// interfere. Points that fail to configure or replay are logged and skipped.
+pub fn run(base: &Config, capture_dir: &Path, initial_balance: Decimal, space: &SweepSpace, points: Vec<Vec<Decimal>>, jobs: usize) -> Vec<SweepResult> {
+    let total = points.len();
+    let queue = Mutex::new(points.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(total));
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| loop {
+                let Some((i, values)) = queue.lock().unwrap().next() else { break };
+                let mut config = base.clone();
+                let outcome = space.axes.iter().zip(&values)
+                    .try_for_each(|((param, _), v)| param.apply(&mut config, *v))
+                    .and_then(|_| backtest::run(config, capture_dir, initial_balance));
+                match outcome {
+                    Ok(report) => {
+                        info!("Sweep {}/{}: {:?} -> PnL {}", i + 1, total, values, report.pnl);
+                        results.lock().unwrap().push(SweepResult::new(space, &values, &report));
+                    }
+                    Err(e) => error!("Sweep {}/{}: {:?} failed: {}", i + 1, total, values, e),
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by(|a, b| b.pnl.cmp(&a.pnl)
+        .then(a.max_drawdown.cmp(&b.max_drawdown))
+        .then(a.params.cmp(&b.params))); // Stable order across runs
+    for (i, result) in results.iter_mut().enumerate() {
+        result.rank = i + 1;
+    }
+    results
+}
+
+/// Results as CSV, one column per swept parameter.
+pub fn to_csv(results: &[SweepResult]) -> String {
+    let mut csv = String::from("rank");
+    if let Some(first) = results.first() {
+        for name in first.params.keys() {
+            write!(csv, ",{}", name).unwrap();
+        }
+    }
+    csv.push_str(",pnl,max_drawdown,attempts,filled,partial,hit_rate,realized_edge\n");
+    for r in results {
+        write!(csv, "{}", r.rank).unwrap();
+        for value in r.params.values() {
+            write!(csv, ",{}", value).unwrap();
+        }
+        writeln!(csv, ",{},{},{},{},{},{},{}",
+            r.pnl, r.max_drawdown, r.attempts, r.filled, r.partial, r.hit_rate.round_dp(4), r.realized_edge.round_dp(4)).unwrap();
+    }
+    csv
+}
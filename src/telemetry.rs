@@ -0,0 +1,115 @@
+use crate::clock::Clock;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Samples older than this are dropped, so no breaker window can look further back.
+pub const RETENTION_SECS: i64 = 3600;
+/// Hard cap on kept order samples, whatever their age.
+const MAX_ORDER_SAMPLES: usize = 1000;
+
+/// Connectivity and order health reported by `MarketMonitor` and
+/// `ExecutionEngine`, read by `CircuitBreakers`.
+pub struct Telemetry {
+    clock: Arc<dyn Clock>,
+    state: Mutex<TelemetryState>,
+}
+
+struct TelemetryState {
+    ws_down_since: Option<DateTime<Utc>>, // None while connected
+    orders: VecDeque<OrderSample>,
+    partial_fills: VecDeque<DateTime<Utc>>,
+}
+
+struct OrderSample {
+    at: DateTime<Utc>,
+    latency: std::time::Duration,
+    ok: bool,
+}
+
+/// Order submissions within a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OrderStats {
+    pub count: usize,
+    pub errors: usize,
+    pub p99_latency_ms: Option<u64>,
+}
+
+impl Telemetry {
+    /// Starts disconnected: a feed that never comes up counts as down from now.
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            state: Mutex::new(TelemetryState {
+                ws_down_since: Some(now),
+                orders: VecDeque::new(),
+                partial_fills: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn ws_connected(&self) {
+        self.state.lock().unwrap().ws_down_since = None;
+    }
+
+    pub fn ws_disconnected(&self) {
+        let now = self.clock.now();
+        self.state.lock().unwrap().ws_down_since.get_or_insert(now);
+    }
+
+    /// How long the market feed has been down, or `None` while connected.
+    pub fn ws_down_for(&self) -> Option<Duration> {
+        let down_since = self.state.lock().unwrap().ws_down_since?;
+        Some(self.clock.now() - down_since)
+    }
+
+    /// One REST order round trip. `ok` is false for transport and HTTP errors,
+    /// not for orders the exchange accepted the request for but declined to fill.
+    pub fn record_order(&self, latency: std::time::Duration, ok: bool) {
+        let at = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        state.orders.push_back(OrderSample { at, latency, ok });
+        if state.orders.len() > MAX_ORDER_SAMPLES {
+            state.orders.pop_front();
+        }
+        Self::prune(&mut state, at);
+    }
+
+    pub fn record_partial_fill(&self) {
+        let at = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        state.partial_fills.push_back(at);
+        Self::prune(&mut state, at);
+    }
+
+    pub fn order_stats(&self, window: Duration) -> OrderStats {
+        let since = self.clock.now() - window;
+        let state = self.state.lock().unwrap();
+        let recent: Vec<&OrderSample> = state.orders.iter().filter(|s| s.at > since).collect();
+        let mut latencies: Vec<u64> = recent.iter().map(|s| s.latency.as_millis() as u64).collect();
+        latencies.sort_unstable();
+        // Nearest-rank percentile
+        let p99 = (!latencies.is_empty()).then(|| latencies[(latencies.len() * 99).div_ceil(100) - 1]);
+        OrderStats {
+            count: recent.len(),
+            errors: recent.iter().filter(|s| !s.ok).count(),
+            p99_latency_ms: p99,
+        }
+    }
+
+    pub fn partial_fills_within(&self, window: Duration) -> usize {
+        let since = self.clock.now() - window;
+        self.state.lock().unwrap().partial_fills.iter().filter(|at| **at > since).count()
+    }
+
+    fn prune(state: &mut TelemetryState, now: DateTime<Utc>) {
+        let cutoff = now - Duration::seconds(RETENTION_SECS);
+        while state.orders.front().is_some_and(|s| s.at <= cutoff) {
+            state.orders.pop_front();
+        }
+        while state.partial_fills.front().is_some_and(|at| *at <= cutoff) {
+            state.partial_fills.pop_front();
+        }
+    }
+}
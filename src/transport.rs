@@ -0,0 +1,124 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use futures_util::{Sink, Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Method};
+use serde::de::DeserializeOwned;
+use std::pin::Pin;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::tungstenite::Error as WsError;
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self { method, url: url.into(), headers: HeaderMap::new(), body: None }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    pub fn post(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::new(Method::POST, url).json_body(body)
+    }
+
+    pub fn delete(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::new(Method::DELETE, url).json_body(body)
+    }
+
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    fn json_body(mut self, body: impl Into<String>) -> Self {
+        self.headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        self.body = Some(body.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn error_for_status(self) -> Result<Self> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            anyhow::bail!("HTTP {}: {}", self.status, self.body)
+        }
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+/// Sends CLOB REST requests. Transport failures are errors; HTTP error
+/// statuses are returned as responses for the caller to judge.
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse>>;
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse>> {
+        Box::pin(async move {
+            let mut builder = self.client.request(request.method, &request.url).headers(request.headers);
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let resp = builder.send().await?;
+            let status = resp.status().as_u16();
+            Ok(HttpResponse { status, body: resp.text().await? })
+        })
+    }
+}
+
+pub type WsSink = Pin<Box<dyn Sink<Message, Error = WsError> + Send>>;
+pub type WsSource = Pin<Box<dyn Stream<Item = Result<Message, WsError>> + Send>>;
+
+/// Opens WebSocket connections, already split into write and read halves.
+pub trait WsConnector: Send + Sync {
+    fn connect(&self, request: Request) -> BoxFuture<'_, Result<(WsSink, WsSource), WsError>>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TungsteniteConnector;
+
+impl WsConnector for TungsteniteConnector {
+    fn connect(&self, request: Request) -> BoxFuture<'_, Result<(WsSink, WsSource), WsError>> {
+        Box::pin(async move {
+            let (ws_stream, _) = connect_async(request).await?;
+            let (write, read) = ws_stream.split();
+            Ok((Box::pin(write) as WsSink, Box::pin(read) as WsSource))
+        })
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use rust_decimal::Decimal;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
@@ -63,6 +64,97 @@ pub enum Side {
     Sell,
 }
 
+/// The CLOB rejects GTD orders expiring within this many seconds of submission.
+pub const MIN_GTD_LEAD_SECS: i64 = 60;
+
+/// Time-in-force of an order as understood by the CLOB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderType {
+    /// Good-Til-Cancelled: rests on the book until filled or cancelled.
+    Gtc,
+    /// Good-Til-Date: rests until filled, cancelled or `expiration` passes.
+    Gtd { expiration: DateTime<Utc> },
+    /// Fill-Or-Kill: fills completely and immediately, or not at all.
+    Fok,
+    /// Fill-And-Kill: fills what it can immediately, the remainder is cancelled.
+    Fak,
+}
+
+impl OrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderType::Gtc => "GTC",
+            OrderType::Gtd { .. } => "GTD",
+            OrderType::Fok => "FOK",
+            OrderType::Fak => "FAK",
+        }
+    }
+
+    /// Unix seconds for the signed order and request body; 0 means no expiry.
+    pub fn expiration_secs(&self) -> u64 {
+        match self {
+            OrderType::Gtd { expiration } => expiration.timestamp().max(0) as u64,
+            _ => 0,
+        }
+    }
+
+    /// FOK/FAK never rest on the book: whatever did not match at once is gone.
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, OrderType::Fok | OrderType::Fak)
+    }
+}
+
+impl std::fmt::Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses a CLOB order type name. GTD comes back with no expiration (the epoch);
+/// it is stamped when an order is built.
+impl std::str::FromStr for OrderType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_uppercase().as_str() {
+            "GTC" => Ok(OrderType::Gtc),
+            "GTD" => Ok(OrderType::Gtd { expiration: DateTime::UNIX_EPOCH }),
+            "FOK" => Ok(OrderType::Fok),
+            "FAK" => Ok(OrderType::Fak),
+            _ => anyhow::bail!("Unknown order type {}: expected FOK, FAK, GTC or GTD", s),
+        }
+    }
+}
+
+/// Book price held tokens are valued at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkPrice {
+    /// Best bid: what the position would fetch if sold now.
+    Bid,
+    /// Bid/ask midpoint, falling back to the bid when one side is empty.
+    Mid,
+}
+
+impl std::str::FromStr for MarkPrice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_uppercase().as_str() {
+            "BID" => Ok(MarkPrice::Bid),
+            "MID" => Ok(MarkPrice::Mid),
+            _ => anyhow::bail!("Unknown mark price {}: expected BID or MID", s),
+        }
+    }
+}
+
+/// One side of an arb as chosen by the strategy.
+#[derive(Debug, Clone)]
+pub struct ArbLeg {
+    pub token_id: String,
+    pub price: Decimal,
+    pub order_type: OrderType,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderRequest {
     pub market_id: String,
@@ -70,11 +162,140 @@ pub struct OrderRequest {
     pub side: Side,
     pub price: Decimal,
     pub size: Decimal,
-    pub order_type: String, // "FOK"
-    pub nonce: u64,
+    pub order_type: OrderType,
+    pub salt: u64,
+    pub nonce: u64, // Exchange nonce, see NonceManager
+}
+
+// CLOB REST Responses
+
+/// Response to `POST /order`. Amounts are decimal strings and may be empty when nothing matched.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OrderResponse {
+    #[serde(default)]
+    pub success: bool,
+    #[serde(default, rename = "errorMsg")]
+    pub error_msg: String,
+    #[serde(default, rename = "orderID")]
+    pub order_id: String,
+    #[serde(default)]
+    pub status: String, // "matched", "live", "delayed", "unmatched"
+    #[serde(default, rename = "makingAmount")]
+    pub making_amount: String,
+    #[serde(default, rename = "takingAmount")]
+    pub taking_amount: String,
+}
+
+/// Which orders a cancel request targets.
+#[derive(Debug, Clone)]
+pub enum CancelScope {
+    Orders(Vec<String>),
+    Market { market_id: String, asset_id: Option<String> },
+    All,
+}
+
+/// Response to the `DELETE` cancel endpoints.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CancelResponse {
+    #[serde(default)]
+    pub canceled: Vec<String>,
+    #[serde(default)]
+    pub not_canceled: HashMap<String, String>, // order_id -> reason
+}
+
+/// Response to `GET /data/order/{id}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenOrder {
+    pub status: String, // "LIVE", "MATCHED", "CANCELED", ...
+    pub size_matched: String,
+    #[serde(default)]
+    pub associate_trades: Vec<String>,
+}
+
+/// Entry of `GET /data/trades`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClobTrade {
+    pub taker_order_id: String,
+    pub price: String,
+    pub size: String,
+    #[serde(default)]
+    pub status: String, // "MATCHED", "MINED", "CONFIRMED", "RETRYING", "FAILED"
+    #[serde(default)]
+    pub maker_orders: Vec<MakerOrderFill>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MakerOrderFill {
+    pub order_id: String,
+    #[serde(default)]
+    pub owner: String, // API key of the maker
+    pub matched_amount: String,
+    pub price: String,
+}
+
+/// What actually happened to one submitted order.
+#[derive(Debug, Clone)]
+pub struct FillReport {
+    pub order_id: Option<String>,
+    pub status: String,
+    pub filled_size: Decimal,
+    pub avg_price: Decimal,
+    pub error: Option<String>,
+}
+
+impl FillReport {
+    pub fn rejected(order_id: Option<String>, error: String) -> Self {
+        Self {
+            order_id,
+            status: "rejected".to_string(),
+            filled_size: Decimal::ZERO,
+            avg_price: Decimal::ZERO,
+            error: Some(error),
+        }
+    }
+
+    pub fn has_fill(&self) -> bool {
+        self.filled_size > Decimal::ZERO
+    }
+}
+
+/// Parses the decimal strings the CLOB uses for amounts, treating "" as zero.
+pub fn parse_amount(s: &str) -> Decimal {
+    s.trim().parse::<Decimal>().unwrap_or(Decimal::ZERO)
+}
+
+/// Converts a USDC amount in base units (6 decimals) to USDC.
+/// Amounts too large for a Decimal (e.g. an unlimited allowance) saturate.
+pub fn usdc_from_base_units(raw: &str) -> Decimal {
+    let raw = raw.trim();
+    match raw.parse::<Decimal>() {
+        Ok(units) => units / Decimal::from(1_000_000),
+        Err(_) if !raw.is_empty() && raw.bytes().all(|b| b.is_ascii_digit()) => Decimal::MAX,
+        Err(_) => Decimal::ZERO,
+    }
+}
+
+/// Response of `GET /balance-allowance` for collateral (amounts in base units).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BalanceAllowanceResponse {
+    #[serde(default)]
+    pub balance: String,
+    #[serde(default)]
+    pub allowance: Option<String>,
+    #[serde(default)]
+    pub allowances: HashMap<String, String>, // Per exchange contract, on newer API versions
+}
+
+/// Our USDC collateral and how much of it the exchange may pull.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollateralBalance {
+    pub balance: Decimal,
+    pub allowance: Decimal,
 }
 
 // EIP-712 Structs
+// Field names must match the on-chain struct for the EIP-712 type hash.
+#[allow(non_snake_case)]
 #[derive(Debug, Clone, Eip712, EthAbiType)]
 #[eip712(
     name = "Polymarket CTF Exchange",
@@ -97,13 +318,18 @@ pub struct Order {
     pub signatureType: u8, // 0 for EOA, 1 for Poly Proxy, 2 for Kernel
 }
 
+/// Outcome of one `execute_arb` call, broadcast for journaling and backtests.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeEvent {
     pub id: Uuid,
     pub market_id: String,
     pub yes_price: Decimal,
     pub no_price: Decimal,
-    pub edge: Decimal,
+    pub edge: Decimal, // Detected: 1 - yes_price - no_price
+    pub size: Decimal,
+    pub filled_size: Decimal, // Hedged quantity (both legs matched)
+    pub cost: Decimal, // Capital committed at the limit prices
+    pub pnl: Decimal, // Realized, including any recovery trades
     pub timestamp: DateTime<Utc>,
     pub status: TradeStatus,
     pub logs: Vec<String>,
@@ -142,6 +368,7 @@ pub struct WsBookUpdate {
     pub asset_id: String,
     pub bids: Vec<WsLevel>,
     pub asks: Vec<WsLevel>,
+    #[allow(dead_code)]
     pub hash: String,
     pub timestamp: String, 
 }
@@ -149,6 +376,62 @@ pub struct WsBookUpdate {
 #[derive(Debug, Deserialize)]
 pub struct WsLevel(String, String); 
 
+// User Channel (authenticated)
+
+#[derive(Debug, Serialize)]
+pub struct WsUserAuth {
+    #[serde(rename = "apiKey")]
+    pub api_key: String,
+    pub secret: String,
+    pub passphrase: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WsUserSubscribeMsg {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub auth: WsUserAuth,
+    pub markets: Vec<String>, // Empty = all markets
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "event_type")]
+pub enum WsUserMessage {
+    #[serde(rename = "order")]
+    Order(WsOrderEvent),
+    #[serde(rename = "trade")]
+    Trade(WsTradeEvent),
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WsOrderEvent {
+    pub id: String,
+    pub market: String,
+    pub asset_id: String,
+    #[serde(rename = "type")]
+    pub event: String, // "PLACEMENT", "UPDATE", "CANCELLATION"
+    pub price: String,
+    pub original_size: String,
+    pub size_matched: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WsTradeEvent {
+    pub id: String,
+    pub market: String,
+    pub asset_id: String,
+    pub status: String, // "MATCHED", "MINED", "CONFIRMED", "RETRYING", "FAILED"
+    pub price: String,
+    pub size: String,
+    pub taker_order_id: String,
+    #[serde(default)]
+    pub trader_side: String, // "TAKER" or "MAKER": which side of the trade we were on
+    #[serde(default)]
+    pub maker_orders: Vec<MakerOrderFill>,
+}
+
 impl WsLevel {
     pub fn to_level(&self) -> Option<Level> {
         let price = self.0.parse::<Decimal>().ok()?;
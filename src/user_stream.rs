@@ -0,0 +1,158 @@
+use crate::types::{WsUserAuth, WsUserMessage, WsUserSubscribeMsg};
+use crate::config::Config;
+use crate::execution::ExecutionEngine;
+use crate::orders::SettlementStatus;
+use crate::risk::RiskManager;
+use crate::transport::{TungsteniteConnector, WsConnector};
+use std::sync::Arc;
+use tracing::{info, error, warn, debug};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use futures_util::{StreamExt, SinkExt};
+use std::time::Duration;
+
+/// Authenticated connection to the CLOB user channel.
+///
+/// Streams our own order placements/cancellations and trade status
+/// transitions, so fills and settlement failures are known without polling.
+pub struct UserStream {
+    config: Config,
+    execution_engine: Arc<ExecutionEngine>,
+    risk_manager: RiskManager,
+    ws: Arc<dyn WsConnector>,
+}
+
+impl UserStream {
+    pub fn new(config: Config, execution_engine: Arc<ExecutionEngine>, risk_manager: RiskManager) -> Self {
+        Self::with_connector(config, execution_engine, risk_manager, Arc::new(TungsteniteConnector))
+    }
+
+    pub fn with_connector(config: Config, execution_engine: Arc<ExecutionEngine>, risk_manager: RiskManager, ws: Arc<dyn WsConnector>) -> Self {
+        Self {
+            config,
+            execution_engine,
+            risk_manager,
+            ws,
+        }
+    }
+
+    pub async fn run_ws_loop(&self) {
+        let url_str = &self.config.user_ws_url;
+        let mut backoff = 1;
+
+        loop {
+            info!("Connecting to User WS: {}", url_str);
+
+            let request = url_str.into_client_request().expect("Failed to build request");
+
+            match self.ws.connect(request).await {
+                Ok((mut write, mut read)) => {
+                    info!("User WebSocket Connected");
+                    backoff = 1;
+
+                    // 1. Authenticate & Subscribe
+                    let sub_msg = WsUserSubscribeMsg {
+                        msg_type: "user".to_string(),
+                        auth: WsUserAuth {
+                            api_key: self.config.api_key.clone(),
+                            secret: self.config.api_secret.clone(),
+                            passphrase: self.config.api_passphrase.clone(),
+                        },
+                        markets: vec![],
+                    };
+                    let json = serde_json::to_string(&sub_msg).unwrap();
+                    if let Err(e) = write.send(Message::Text(json)).await {
+                        error!("Failed to send user subscribe: {}", e);
+                    } else {
+                        // 2. Heartbeat & Read Loop
+                        let mut ping_interval = tokio::time::interval(Duration::from_secs(20));
+
+                        loop {
+                            tokio::select! {
+                                _ = ping_interval.tick() => {
+                                    if let Err(e) = write.send(Message::Ping(vec![])).await {
+                                        error!("Failed to send Ping on user WS: {}", e);
+                                        break;
+                                    }
+                                }
+                                msg = read.next() => {
+                                    match msg {
+                                        Some(Ok(Message::Text(text))) => self.handle_message(&text),
+                                        Some(Ok(Message::Ping(payload))) => {
+                                            if let Err(e) = write.send(Message::Pong(payload)).await {
+                                                error!("Failed to send Pong on user WS: {}", e);
+                                                break;
+                                            }
+                                        }
+                                        Some(Ok(Message::Close(frame))) => {
+                                            warn!("User WS Closed by server: {:?}", frame);
+                                            break;
+                                        }
+                                        Some(Ok(_)) => {}
+                                        Some(Err(e)) => {
+                                            error!("User WS Read Error: {}", e);
+                                            break;
+                                        }
+                                        None => {
+                                            warn!("User WS Stream Ended");
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("User WS Connection Failed: {}", e);
+                }
+            }
+
+            let wait_secs = std::cmp::min(backoff, 60);
+            warn!("Reconnecting user WS in {}s...", wait_secs);
+            tokio::time::sleep(Duration::from_secs(wait_secs)).await;
+            backoff *= 2;
+        }
+    }
+
+    fn handle_message(&self, text: &str) {
+        if text == "[]" { return; }
+
+        // Events arrive either singly or batched in an array
+        let parsed = if text.starts_with('[') {
+            serde_json::from_str::<Vec<WsUserMessage>>(text)
+        } else {
+            serde_json::from_str::<WsUserMessage>(text).map(|m| vec![m])
+        };
+
+        match parsed {
+            Ok(messages) => {
+                for message in messages {
+                    self.dispatch(message);
+                }
+            }
+            Err(e) => {
+                error!("Failed to parse user WS message: {} | Text: {}", e, text);
+            }
+        }
+    }
+
+    fn dispatch(&self, message: WsUserMessage) {
+        match message {
+            WsUserMessage::Order(event) => {
+                info!("Order {} {} on {}/{}: matched {}/{} @ {}", event.event, event.id, event.market, event.asset_id, event.size_matched, event.original_size, event.price);
+                self.execution_engine.on_order_event(&event);
+            }
+            WsUserMessage::Trade(event) => {
+                info!("Trade {} {}: {} @ {} on {}", event.id, event.status, event.size, event.price, event.asset_id);
+                self.execution_engine.on_trade_event(&event);
+                if let Some(status) = SettlementStatus::from_clob_status(&event.status) {
+                    self.risk_manager.on_trade_status(&event.id, &event.market, status);
+                }
+            }
+            WsUserMessage::Unknown => {
+                debug!("Unknown user WS message");
+            }
+        }
+    }
+}
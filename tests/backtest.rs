@@ -0,0 +1,94 @@
+//! Backtests over synthetic captures.
+
+mod support;
+
+use polymarket_arb_bot::backtest;
+use polymarket_arb_bot::types::TradeStatus;
+use rust_decimal::Decimal;
+use support::mock_clob::BookFrame;
+use support::{fixture_crypto_market, test_config, write_capture};
+
+fn normalize(frames: &mut Vec<BookFrame>, yes: &str, no: &str) {
+    for _ in 0..3 {
+        frames.push(BookFrame::new(yes, vec![("0.49", "100")], vec![("0.50", "100")]));
+        frames.push(BookFrame::new(no, vec![("0.49", "100")], vec![("0.50", "100")]));
+    }
+}
+
+fn dislocate(frames: &mut Vec<BookFrame>, yes: &str) {
+    frames.push(BookFrame::new(yes, vec![("0.39", "100")], vec![("0.40", "100")]));
+}
+
+/// Two dislocations 8 frames (8s of capture time) apart.
+fn two_arbs(yes: &str, no: &str) -> Vec<BookFrame> {
+    let mut frames = Vec::new();
+    normalize(&mut frames, yes, no);
+    dislocate(&mut frames, yes);
+    normalize(&mut frames, yes, no);
+    dislocate(&mut frames, yes);
+    frames
+}
+
+#[test]
+fn reports_fills_and_edge() {
+    let (_, yes, no) = fixture_crypto_market();
+    let mut frames = Vec::new();
+    normalize(&mut frames, &yes, &no);
+    dislocate(&mut frames, &yes);
+    let dir = write_capture(&frames, 1000);
+
+    let report = backtest::run(test_config("http://unused", "ws://unused"), &dir, Decimal::from(1000)).unwrap();
+
+    assert_eq!(report.attempts, 1);
+    assert_eq!(report.filled, 1);
+    assert_eq!(report.hit_rate, Decimal::ONE);
+    assert_eq!(report.trades[0].status, TradeStatus::Filled);
+    assert_eq!(report.avg_detected_edge, Decimal::new(1, 1));
+    assert_eq!(report.realized_edge, Decimal::new(1, 1));
+    assert_eq!(report.capital_committed, Decimal::new(9, 0));
+    assert_eq!(report.pnl, Decimal::ONE);
+    assert_eq!(report.max_drawdown, Decimal::ZERO);
+    assert_eq!(report.pnl_curve.len(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cooldown_runs_on_capture_time() {
+    let (_, yes, no) = fixture_crypto_market();
+    let dir = write_capture(&two_arbs(&yes, &no), 1000);
+
+    // The second dislocation comes 7s after the first trade
+    let mut config = test_config("http://unused", "ws://unused");
+    config.trade_cooldown_ms = 30_000;
+    let report = backtest::run(config.clone(), &dir, Decimal::from(1000)).unwrap();
+    assert_eq!(report.filled, 1);
+
+    config.trade_cooldown_ms = 5_000;
+    let report = backtest::run(config, &dir, Decimal::from(1000)).unwrap();
+    assert_eq!(report.filled, 2);
+    assert_eq!(report.pnl, Decimal::from(2));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn latency_misses_fleeting_dislocations() {
+    let (_, yes, no) = fixture_crypto_market();
+    let mut frames = Vec::new();
+    normalize(&mut frames, &yes, &no);
+    dislocate(&mut frames, &yes);
+    // The cheap YES ask is gone 10ms later
+    frames.push(BookFrame::new(&yes, vec![("0.49", "100")], vec![("0.50", "100")]));
+    let dir = write_capture(&frames, 10);
+
+    let mut config = test_config("http://unused", "ws://unused");
+    config.paper_latency_ms = 50;
+    let report = backtest::run(config, &dir, Decimal::from(1000)).unwrap();
+
+    assert_eq!(report.attempts, 1);
+    assert_eq!(report.filled, 0);
+    assert_eq!(report.pnl, Decimal::ZERO);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,109 @@
+//! Connectivity and execution-quality circuit breakers.
+
+mod support;
+
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use polymarket_arb_bot::breakers::{BreakerLimits, CircuitBreakers};
+use polymarket_arb_bot::clock::ManualClock;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::safe_mode::SafeModeReason;
+use polymarket_arb_bot::telemetry::Telemetry;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+use support::fakes::{FakeHttp, FakeWs};
+use support::test_config;
+
+fn risk_manager() -> RiskManager {
+    let config = test_config("http://fake", "ws://fake");
+    RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct)
+        .with_auto_recover(config.safe_mode_auto_recover)
+}
+
+fn limits() -> BreakerLimits {
+    BreakerLimits::from_config(&test_config("http://fake", "ws://fake"))
+}
+
+fn active(risk_manager: &RiskManager) -> Vec<SafeModeReason> {
+    risk_manager.halts().iter().map(|h| h.reason).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn market_feed_outage_halts_until_reconnected() {
+    let ws = Arc::new(FakeWs::new(0));
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let http = Arc::new(FakeHttp::new(|_: &HttpRequest| HttpResponse { status: 404, body: String::new() }));
+    let monitor = Arc::new(MarketMonitor::with_transport(test_config("http://fake", "ws://fake"), clock.clone(), http, ws.clone()));
+    let risk_manager = risk_manager();
+    let breakers = CircuitBreakers::new(monitor.telemetry(), risk_manager.clone(), limits());
+
+    let m = monitor.clone();
+    tokio::spawn(async move { m.run_ws_loop().await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    clock.advance(ChronoDuration::seconds(60));
+    breakers.check();
+    assert!(!risk_manager.is_safe_mode());
+
+    // Down 9s: tolerated. Down 10s: halted.
+    ws.drop_session();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    clock.advance(ChronoDuration::seconds(9));
+    breakers.check();
+    assert!(!risk_manager.is_safe_mode());
+    clock.advance(ChronoDuration::seconds(1));
+    breakers.check();
+    assert_eq!(active(&risk_manager), [SafeModeReason::StaleData]);
+
+    // The 1s backoff elapses, the feed comes back and the halt clears itself
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(ws.is_connected());
+    breakers.check();
+    assert!(!risk_manager.is_safe_mode());
+}
+
+#[test]
+fn order_errors_latency_and_partial_fills_trip_their_breakers() {
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let telemetry = Arc::new(Telemetry::new(clock.clone()));
+    telemetry.ws_connected();
+    let risk_manager = risk_manager();
+    let breakers = CircuitBreakers::new(telemetry.clone(), risk_manager.clone(), limits());
+
+    // 3 failures in 5 requests is over the 50% limit; 4 fast requests are too few to judge
+    for ok in [true, false, false, false] {
+        telemetry.record_order(Duration::from_millis(50), ok);
+    }
+    breakers.check();
+    assert!(active(&risk_manager).is_empty());
+    telemetry.record_order(Duration::from_millis(50), true);
+    breakers.check();
+    assert_eq!(active(&risk_manager), [SafeModeReason::OrderErrors]);
+
+    // Once the failures age out of the window the halt clears
+    clock.advance(ChronoDuration::seconds(301));
+    breakers.check();
+    assert!(active(&risk_manager).is_empty());
+
+    // p99 over 5 samples is the slowest one
+    for ms in [100, 120, 90, 110, 2500] {
+        telemetry.record_order(Duration::from_millis(ms), true);
+    }
+    breakers.check();
+    assert_eq!(telemetry.order_stats(ChronoDuration::seconds(300)).p99_latency_ms, Some(2500));
+    assert_eq!(active(&risk_manager), [SafeModeReason::OrderLatency]);
+    clock.advance(ChronoDuration::seconds(301));
+    breakers.check();
+
+    // A fourth emergency within the hour halts, and stays halted for the operator
+    for _ in 0..4 {
+        telemetry.record_partial_fill();
+        clock.advance(ChronoDuration::minutes(10));
+    }
+    breakers.check();
+    assert_eq!(active(&risk_manager), [SafeModeReason::PartialFillRate]);
+    clock.advance(ChronoDuration::hours(2));
+    breakers.check();
+    assert_eq!(active(&risk_manager), [SafeModeReason::PartialFillRate]);
+}
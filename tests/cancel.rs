@@ -0,0 +1,134 @@
+//! Cancellation by id, batch, market and account, against the REST endpoints.
+
+mod support;
+
+use polymarket_arb_bot::clock::SystemClock;
+use polymarket_arb_bot::execution::ExecutionEngine;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::orders::OrderState;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse};
+use polymarket_arb_bot::types::{OrderRequest, OrderType, Side};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use support::fakes::{ok_json, FakeHttp, FakeWs};
+use support::test_config;
+
+/// Cancels whatever it is asked to, except order "c", which already matched.
+fn exchange(cancel_all_down: Arc<AtomicBool>) -> impl Fn(&HttpRequest) -> HttpResponse + Send + Sync {
+    move |req: &HttpRequest| {
+        if req.method != Method::DELETE {
+            return HttpResponse { status: 404, body: String::new() };
+        }
+        let body: Value = req.body.as_deref().filter(|b| !b.is_empty()).map(|b| serde_json::from_str(b).unwrap()).unwrap_or(Value::Null);
+        let ids: Vec<String> = match req.url.rsplit('/').next().unwrap() {
+            "order" => vec![body["orderID"].as_str().unwrap().to_string()],
+            "orders" => serde_json::from_value(body).unwrap(),
+            "cancel-market-orders" => vec!["m1-order".to_string()],
+            "cancel-all" if cancel_all_down.load(Ordering::SeqCst) => {
+                return HttpResponse { status: 503, body: "maintenance".to_string() };
+            }
+            "cancel-all" => vec!["a".to_string(), "b".to_string()],
+            _ => return HttpResponse { status: 404, body: String::new() },
+        };
+        let (refused, canceled): (Vec<String>, Vec<String>) = ids.into_iter().partition(|id| id == "c");
+        let not_canceled: serde_json::Map<String, Value> = refused.into_iter().map(|id| (id, json!("order already matched"))).collect();
+        ok_json(json!({ "canceled": canceled, "not_canceled": not_canceled }))
+    }
+}
+
+fn live_engine(cancel_all_down: Arc<AtomicBool>) -> (Arc<FakeHttp>, ExecutionEngine) {
+    let config = test_config("http://fake", "ws://fake");
+    let http = Arc::new(FakeHttp::new(exchange(cancel_all_down)));
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), Arc::new(SystemClock), http.clone(), Arc::new(FakeWs::new(0))));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    (http, ExecutionEngine::new(config, risk_manager, monitor))
+}
+
+/// Tracks an order the exchange has accepted.
+fn resting(engine: &ExecutionEngine, order_id: &str) {
+    let req = OrderRequest {
+        market_id: "m1".to_string(),
+        token_id: "yes-1".to_string(),
+        side: Side::Buy,
+        price: "0.40".parse().unwrap(),
+        size: Decimal::from(10),
+        order_type: OrderType::Gtc,
+        salt: 1,
+        nonce: 0,
+    };
+    engine.orders().create(order_id, &req);
+    engine.orders().transition(order_id, OrderState::Signed).unwrap();
+    engine.orders().transition(order_id, OrderState::Submitted).unwrap();
+}
+
+fn state(engine: &ExecutionEngine, order_id: &str) -> OrderState {
+    engine.orders().get(order_id).unwrap().state
+}
+
+#[tokio::test]
+async fn each_scope_hits_its_endpoint_and_updates_the_registry() {
+    let (http, engine) = live_engine(Arc::new(AtomicBool::new(false)));
+    for id in ["a", "b", "c", "m1-order"] {
+        resting(&engine, id);
+    }
+
+    let resp = engine.cancel_order("a").await.unwrap();
+    assert_eq!(resp.canceled, ["a"]);
+    assert_eq!(state(&engine, "a"), OrderState::Cancelled);
+
+    // A refusal leaves the order as it was
+    let resp = engine.cancel_orders(&["b".to_string(), "c".to_string()]).await.unwrap();
+    assert_eq!(resp.canceled, ["b"]);
+    assert_eq!(resp.not_canceled["c"], "order already matched");
+    assert_eq!(state(&engine, "b"), OrderState::Cancelled);
+    assert_eq!(state(&engine, "c"), OrderState::Submitted);
+
+    // A late fill wins over a cancel the exchange reports afterwards
+    engine.orders().record_matched("m1-order", Decimal::from(10), None).unwrap();
+    engine.cancel_market("m1", Some("yes-1")).await.unwrap();
+    assert_eq!(state(&engine, "m1-order"), OrderState::Filled);
+
+    let sent: Vec<(String, Value)> = http.requests().iter().map(|r| {
+        assert_eq!(r.method, Method::DELETE);
+        assert!(r.headers.contains_key("POLY_SIGNATURE"), "{} unsigned", r.url);
+        (r.url.clone(), serde_json::from_str(r.body.as_deref().unwrap()).unwrap())
+    }).collect();
+    assert_eq!(sent, [
+        ("http://fake/order".to_string(), json!({ "orderID": "a" })),
+        ("http://fake/orders".to_string(), json!(["b", "c"])),
+        ("http://fake/cancel-market-orders".to_string(), json!({ "market": "m1", "asset_id": "yes-1" })),
+    ]);
+}
+
+#[tokio::test]
+async fn cancel_all_falls_back_to_the_tracked_open_orders() {
+    let cancel_all_down = Arc::new(AtomicBool::new(true));
+    let (http, engine) = live_engine(cancel_all_down.clone());
+
+    // Nothing tracked to fall back on: the failure is reported
+    assert!(engine.cancel_all().await.is_err());
+
+    resting(&engine, "a");
+    resting(&engine, "b");
+    resting(&engine, "done");
+    engine.orders().transition("done", OrderState::Cancelled).unwrap();
+
+    let resp = engine.cancel_all().await.unwrap();
+    let mut canceled = resp.canceled.clone();
+    canceled.sort();
+    assert_eq!(canceled, ["a", "b"]);
+    assert_eq!(state(&engine, "a"), OrderState::Cancelled);
+    let last = http.requests().pop().unwrap();
+    assert_eq!(last.url, "http://fake/orders");
+    assert!(!last.body.unwrap().contains("done"));
+
+    // Once the exchange-wide call works again it is used directly
+    cancel_all_down.store(false, Ordering::SeqCst);
+    resting(&engine, "c");
+    engine.cancel_all().await.unwrap();
+    assert_eq!(http.requests().pop().unwrap().url, "http://fake/cancel-all");
+}
@@ -0,0 +1,54 @@
+//! Record a capture, then replay it into a fresh `MarketMonitor`.
+
+mod support;
+
+use chrono::{Duration, TimeZone, Utc};
+use polymarket_arb_bot::capture::{read_frames, CaptureWriter};
+use polymarket_arb_bot::market::MarketMonitor;
+use rust_decimal::Decimal;
+use serde_json::json;
+use support::{fixture_crypto_market, market_fixture, test_config};
+
+fn book_frame(asset_id: &str, ask: &str) -> String {
+    json!({
+        "event_type": "book",
+        "asset_id": asset_id,
+        "bids": [["0.30", "100"]],
+        "asks": [[ask, "100"]],
+        "hash": "",
+        "timestamp": "1700000000000",
+    }).to_string()
+}
+
+#[tokio::test]
+async fn replays_rotated_capture_in_order() {
+    let dir = std::env::temp_dir().join(format!("capture-test-{}", uuid::Uuid::new_v4()));
+    let (market_id, yes, no) = fixture_crypto_market();
+    let start = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+
+    {
+        let mut writer = CaptureWriter::new(&dir, 1).unwrap();
+        writer.write_markets(start, &std::fs::read_to_string(market_fixture()).unwrap()).unwrap();
+        // One second apart with a 1s rotation: every frame lands in its own file
+        for (i, (token, ask)) in [(&yes, "0.50"), (&no, "0.50"), (&yes, "0.45")].iter().enumerate() {
+            writer.write_frame(start + Duration::seconds(i as i64), &book_frame(token, ask)).unwrap();
+        }
+    }
+
+    let ws_files = std::fs::read_dir(&dir).unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("ws-"))
+        .count();
+    assert_eq!(ws_files, 3);
+
+    let frames: Vec<_> = read_frames(&dir).unwrap().collect();
+    assert_eq!(frames.len(), 3);
+    assert!(frames.windows(2).all(|w| w[0].ts < w[1].ts));
+
+    let monitor = MarketMonitor::new(test_config("http://unused", "ws://unused"));
+    monitor.replay(&dir, 0.0).await.unwrap();
+
+    assert_eq!(monitor.get_market_tokens(&market_id), Some((yes.clone(), no.clone())));
+    assert_eq!(monitor.get_best_asks(&yes, &no), Some((Decimal::new(45, 2), Decimal::new(50, 2))));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
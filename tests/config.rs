@@ -0,0 +1,51 @@
+//! Startup validation of the environment.
+
+use polymarket_arb_bot::config::Config;
+use polymarket_arb_bot::types::{MarkPrice, OrderType};
+
+/// Loads the offline config with `vars` set, unsetting them again afterwards.
+fn load_with(vars: &[(&str, &str)]) -> anyhow::Result<Config> {
+    for (name, value) in vars {
+        std::env::set_var(name, value);
+    }
+    let config = Config::for_simulation();
+    for (name, _) in vars {
+        std::env::remove_var(name);
+    }
+    config
+}
+
+// One test so the environment is never changed under a concurrent load
+#[test]
+fn settings_are_validated_at_load() {
+    assert_eq!(load_with(&[]).unwrap().gtd_ttl_secs, 120);
+    assert_eq!(load_with(&[("GTD_TTL_SECS", "61")]).unwrap().gtd_ttl_secs, 61);
+    for short in ["60", "30", "-5"] {
+        let err = load_with(&[("GTD_TTL_SECS", short)]).unwrap_err().to_string();
+        assert!(err.contains("GTD_TTL_SECS") && err.contains("60s"), "{}", err);
+    }
+
+    assert_eq!(load_with(&[]).unwrap().arb_order_type, OrderType::Fok);
+    assert_eq!(load_with(&[("ARB_ORDER_TYPE", "fak")]).unwrap().arb_order_type, OrderType::Fak);
+    assert!(matches!(load_with(&[("ARB_ORDER_TYPE", "GTD")]).unwrap().arb_order_type, OrderType::Gtd { .. }));
+    let err = format!("{:#}", load_with(&[("ARB_ORDER_TYPE", "IOC")]).unwrap_err());
+    assert!(err.contains("ARB_ORDER_TYPE") && err.contains("IOC"), "{}", err);
+
+    assert_eq!(load_with(&[]).unwrap().mark_price, MarkPrice::Bid);
+    assert_eq!(load_with(&[("MARK_PRICE", "mid")]).unwrap().mark_price, MarkPrice::Mid);
+    assert!(load_with(&[("MARK_PRICE", "LAST")]).is_err());
+
+    // Breaker windows can't reach back past the hour of telemetry kept
+    assert_eq!(load_with(&[("BREAKER_WINDOW_SECS", "3600")]).unwrap().breaker_window_secs, 3600);
+    assert_eq!(load_with(&[("BREAKER_WINDOW_SECS", "86400")]).unwrap().breaker_window_secs, 3600);
+
+    // The unauthenticated control port only listens when asked to
+    assert_eq!(load_with(&[]).unwrap().control_addr, None);
+    assert_eq!(load_with(&[("CONTROL_ADDR", "")]).unwrap().control_addr, None);
+    assert_eq!(load_with(&[("CONTROL_ADDR", "127.0.0.1:7878")]).unwrap().control_addr.as_deref(), Some("127.0.0.1:7878"));
+
+    // Nor is a sentinel file watched in whatever directory the bot started from
+    assert_eq!(load_with(&[]).unwrap().kill_switch_file, None);
+    assert_eq!(load_with(&[("KILL_SWITCH_FILE", "")]).unwrap().kill_switch_file, None);
+    assert_eq!(load_with(&[("KILL_SWITCH_FILE", "/run/bot/kill")]).unwrap().kill_switch_file.as_deref(), Some("/run/bot/kill"));
+}
@@ -0,0 +1,99 @@
+//! Typed safe-mode halts, operator recovery over the control port, and the audit log.
+
+use polymarket_arb_bot::control::ControlServer;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::safe_mode::{AuditEntry, SafeModeAction, SafeModeReason, SafeModeScope};
+use polymarket_arb_bot::types::CollateralBalance;
+use rust_decimal::Decimal;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::TcpStream;
+
+fn risk_manager() -> RiskManager {
+    RiskManager::new(Decimal::from(1000), Decimal::new(2, 2), Decimal::new(1, 2))
+}
+
+fn collateral(balance: i64) -> CollateralBalance {
+    CollateralBalance { balance: Decimal::from(balance), allowance: Decimal::from(1000) }
+}
+
+/// Sends one command and collects the reply up to its OK/ERR line.
+async fn command(lines: &mut Lines<BufReader<OwnedReadHalf>>, writer: &mut tokio::net::tcp::OwnedWriteHalf, cmd: &str) -> Vec<String> {
+    writer.write_all(format!("{}\n", cmd).as_bytes()).await.unwrap();
+    let mut reply = Vec::new();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        let done = line.starts_with("OK") || line.starts_with("ERR");
+        reply.push(line);
+        if done {
+            break;
+        }
+    }
+    reply
+}
+
+#[tokio::test]
+async fn operator_halts_and_resumes_over_the_control_port() {
+    let audit = std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()));
+    let risk_manager = risk_manager().with_audit_log(&audit);
+    let server = ControlServer::bind("127.0.0.1:0", risk_manager.clone()).await.unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    let (reader, mut writer) = TcpStream::connect(addr).await.unwrap().into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    assert_eq!(command(&mut lines, &mut writer, "status").await, ["OK trading"]);
+
+    // A market halt blocks only that market
+    let reply = command(&mut lines, &mut writer, "halt btc-1 -- checking the oracle").await;
+    assert_eq!(reply, ["OK halted market btc-1"]);
+    assert!(!risk_manager.is_safe_mode());
+    assert_eq!(risk_manager.market_halt("btc-1").unwrap().reason, SafeModeReason::Manual);
+    assert!(risk_manager.market_halt("eth-1").is_none());
+
+    risk_manager.enter_safe_mode(SafeModeReason::PartialFill, SafeModeScope::Global, "One-sided fill on eth-1");
+    assert!(risk_manager.market_halt("eth-1").is_some());
+    let reply = command(&mut lines, &mut writer, "status").await;
+    assert_eq!(reply.len(), 3);
+    assert!(reply[1].starts_with("HALT partial_fill (global)"), "{:?}", reply);
+    assert_eq!(reply[2], "OK 2 active");
+
+    assert_eq!(command(&mut lines, &mut writer, "resume stale_data").await, ["ERR no matching halt"]);
+    assert!(command(&mut lines, &mut writer, "resume bogus").await[0].starts_with("ERR Unknown safe mode reason"));
+
+    let reply = command(&mut lines, &mut writer, "resume partial_fill global").await;
+    assert_eq!(reply.last().unwrap(), "OK cleared 1");
+    assert!(!risk_manager.is_safe_mode());
+    assert!(risk_manager.market_halt("btc-1").is_some());
+
+    assert_eq!(command(&mut lines, &mut writer, "resume all").await.last().unwrap(), "OK cleared 1");
+    assert!(risk_manager.halts().is_empty());
+
+    let entries: Vec<AuditEntry> = std::fs::read_to_string(&audit).unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let trail: Vec<_> = entries.iter().map(|e| (e.action, e.reason, e.by.as_str())).collect();
+    assert_eq!(trail, [
+        (SafeModeAction::Enter, SafeModeReason::Manual, "operator"),
+        (SafeModeAction::Enter, SafeModeReason::PartialFill, "risk"),
+        (SafeModeAction::Recover, SafeModeReason::PartialFill, "operator"),
+        (SafeModeAction::Recover, SafeModeReason::Manual, "operator"),
+    ]);
+    assert_eq!(entries[0].detail, "checking the oracle");
+    std::fs::remove_file(audit).unwrap();
+}
+
+#[test]
+fn drift_auto_recovers_only_when_configured() {
+    let manual = risk_manager();
+    let auto = risk_manager().with_auto_recover(vec![SafeModeReason::ReconciliationDrift]);
+    for risk_manager in [&manual, &auto] {
+        risk_manager.set_starting_balance(collateral(100));
+        risk_manager.reconcile_balance(collateral(50), Decimal::ONE);
+        assert_eq!(risk_manager.safe_mode().unwrap().reason, SafeModeReason::ReconciliationDrift);
+        risk_manager.reconcile_balance(collateral(50), Decimal::ONE);
+    }
+    assert!(manual.is_safe_mode());
+    assert!(!auto.is_safe_mode());
+}
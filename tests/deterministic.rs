@@ -0,0 +1,206 @@
+//! Virtual time and fake transports: backoff, cooldowns and signing without a network.
+
+mod support;
+
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use polymarket_arb_bot::clock::{Clock, ManualClock};
+use polymarket_arb_bot::execution::ExecutionEngine;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::strategy::StrategyEngine;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse};
+use polymarket_arb_bot::types::TradeStatus;
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use support::fakes::{ok_json, FakeHttp, FakeWs};
+use support::mock_clob::BookFrame;
+use support::{fixture_crypto_market, market_fixture, test_config};
+
+fn markets_only(req: &HttpRequest) -> HttpResponse {
+    if req.url.contains("/markets") {
+        ok_json(serde_json::from_str(&std::fs::read_to_string(market_fixture()).unwrap()).unwrap())
+    } else {
+        HttpResponse { status: 404, body: String::new() }
+    }
+}
+
+/// Fills every leg of a batch in full at its limit price.
+fn fill_all(req: &HttpRequest) -> HttpResponse {
+    if req.method == Method::POST && req.url.ends_with("/orders") {
+        let legs: Vec<Value> = serde_json::from_str(req.body.as_deref().unwrap()).unwrap();
+        return ok_json(Value::Array(legs.iter().map(|o| {
+            let price: Decimal = o["price"].as_str().unwrap().parse().unwrap();
+            let size: Decimal = o["size"].as_str().unwrap().parse().unwrap();
+            json!({ "success": true, "status": "matched", "makingAmount": (price * size).to_string(), "takingAmount": size.to_string() })
+        }).collect()));
+    }
+    markets_only(req)
+}
+
+fn push_arb(ws: &FakeWs, yes: &str, no: &str) {
+    for _ in 0..3 {
+        ws.push(BookFrame::new(yes, vec![("0.49", "100")], vec![("0.50", "100")]).to_ws_json());
+        ws.push(BookFrame::new(no, vec![("0.49", "100")], vec![("0.50", "100")]).to_ws_json());
+    }
+    ws.push(BookFrame::new(yes, vec![("0.39", "100")], vec![("0.40", "100")]).to_ws_json());
+}
+
+/// Lets every task run until the runtime is idle (the clock is paused).
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconnects_with_exponential_backoff() {
+    let ws = Arc::new(FakeWs::new(3));
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let config = test_config("http://fake", "ws://fake");
+    let monitor = Arc::new(MarketMonitor::with_transport(config, clock, Arc::new(FakeHttp::new(markets_only)), ws.clone()));
+
+    let m = monitor.clone();
+    tokio::spawn(async move { m.run_ws_loop().await });
+    tokio::time::sleep(Duration::from_secs(10)).await;
+
+    // Three refusals, waiting 1s, 2s and 4s, then connected
+    let times = ws.connect_times();
+    let gaps: Vec<u64> = times.windows(2).map(|w| (w[1] - w[0]).as_secs()).collect();
+    assert_eq!(gaps, vec![1, 2, 4]);
+    assert!(ws.is_connected());
+
+    // Connected at 7s and dropped at 10s: the reset backoff reconnects at 11s
+    ws.drop_session();
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let times = ws.connect_times();
+    assert_eq!(times.len(), 5);
+    assert_eq!((times[4] - times[3]).as_secs(), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn cooldown_follows_injected_clock() {
+    let (_, yes, no) = fixture_crypto_market();
+    let ws = Arc::new(FakeWs::new(0));
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let mut config = test_config("http://fake", "ws://fake");
+    config.paper_trading = true;
+    config.trade_cooldown_ms = 30_000;
+
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), clock.clone(), Arc::new(FakeHttp::new(markets_only)), ws.clone()));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), monitor.clone()));
+    let strategy = StrategyEngine::new(monitor.clone(), engine.clone(), config);
+    let mut trades = engine.trade_tx.subscribe();
+
+    monitor.start_market_discovery().await;
+    tokio::spawn(async move { strategy.run().await });
+    let m = monitor.clone();
+    tokio::spawn(async move { m.run_ws_loop().await });
+    settle().await;
+
+    push_arb(&ws, &yes, &no);
+    settle().await;
+    let first = trades.try_recv().unwrap();
+    assert_eq!(first.status, TradeStatus::Filled);
+    assert_eq!(first.timestamp, clock.now());
+
+    // 10s later by the injected clock: still cooling down
+    clock.advance(ChronoDuration::seconds(10));
+    push_arb(&ws, &yes, &no);
+    settle().await;
+    assert!(trades.try_recv().is_err());
+
+    // 31s after the trade: re-entry allowed
+    clock.advance(ChronoDuration::seconds(21));
+    push_arb(&ws, &yes, &no);
+    settle().await;
+    assert_eq!(trades.try_recv().unwrap().status, TradeStatus::Filled);
+    assert_eq!(risk_manager.daily_pnl(), Decimal::from(2));
+}
+
+#[tokio::test(start_paused = true)]
+async fn signs_and_stamps_orders_with_injected_clock() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let ws = Arc::new(FakeWs::new(0));
+    let http = Arc::new(FakeHttp::new(fill_all));
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let config = test_config("http://fake", "ws://fake");
+
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), clock.clone(), http.clone(), ws.clone()));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), monitor.clone()));
+    let strategy = StrategyEngine::new(monitor.clone(), engine.clone(), config);
+
+    monitor.start_market_discovery().await;
+    tokio::spawn(async move { strategy.run().await });
+    let m = monitor.clone();
+    tokio::spawn(async move { m.run_ws_loop().await });
+    settle().await;
+    assert!(ws.sent_text().iter().any(|t| t.contains(&yes) && t.contains(&no)));
+
+    push_arb(&ws, &yes, &no);
+    settle().await;
+    assert_eq!(risk_manager.daily_pnl(), Decimal::ONE);
+
+    let batch = http.requests().into_iter().find(|r| r.url == "http://fake/orders").expect("batch posted");
+    assert_eq!(batch.headers["POLY_TIMESTAMP"], "1700000000");
+    assert!(batch.headers.contains_key("POLY_SIGNATURE"));
+
+    assert!(engine.orders().open_orders(Some(&market_id)).is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconciles_balance_against_fills() {
+    let (_, yes, no) = fixture_crypto_market();
+    // Exchange-side USDC balance in base units
+    let reported = Arc::new(std::sync::Mutex::new("100000000".to_string()));
+    let balance = reported.clone();
+    let http = Arc::new(FakeHttp::new(move |req| {
+        if req.url.contains("/balance-allowance") {
+            return ok_json(json!({
+                "balance": *balance.lock().unwrap(),
+                "allowances": {
+                    "0x4bfb41d5b3570defd03c39a9a4d8de6bd8b8982e": "50000000",
+                    "0xC5d563A36AE78145C45a50134d48A1215220f80a": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                },
+            }));
+        }
+        fill_all(req)
+    }));
+    let ws = Arc::new(FakeWs::new(0));
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let mut config = test_config("http://fake", "ws://fake");
+    config.max_trade_capital_pct = Decimal::new(10, 2);
+
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), clock, http, ws.clone()));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), monitor.clone()));
+    let strategy = StrategyEngine::new(monitor.clone(), engine.clone(), config.clone());
+
+    let collateral = engine.fetch_collateral().await.unwrap();
+    assert_eq!(collateral.balance, Decimal::from(100));
+    assert_eq!(collateral.allowance, Decimal::from(50)); // The CTF Exchange's, not the unlimited one
+    risk_manager.set_starting_balance(collateral);
+
+    monitor.start_market_discovery().await;
+    tokio::spawn(async move { strategy.run().await });
+    let m = monitor.clone();
+    tokio::spawn(async move { m.run_ws_loop().await });
+    settle().await;
+    push_arb(&ws, &yes, &no);
+    settle().await;
+
+    // Bought 10 YES @ 0.40 and 10 NO @ 0.50
+    assert_eq!(risk_manager.cash(), Some(Decimal::from(91)));
+
+    *reported.lock().unwrap() = "91000000".to_string();
+    let drift = risk_manager.reconcile_balance(engine.fetch_collateral().await.unwrap(), config.balance_drift_tolerance);
+    assert_eq!(drift, Decimal::ZERO);
+
+    // Money we can't account for (e.g. a withdrawal) is drift
+    *reported.lock().unwrap() = "41000000".to_string();
+    let drift = risk_manager.reconcile_balance(engine.fetch_collateral().await.unwrap(), config.balance_drift_tolerance);
+    assert_eq!(drift, Decimal::from(-50));
+    assert_eq!(risk_manager.cash(), Some(Decimal::from(41)));
+}
@@ -0,0 +1,194 @@
+//! Discovery -> WS -> strategy -> execution against the mock CLOB.
+
+mod support;
+
+use polymarket_arb_bot::execution::ExecutionEngine;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::orders::SettlementStatus;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::strategy::StrategyEngine;
+use polymarket_arb_bot::types::{TradeEvent, TradeStatus, WsTradeEvent};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use support::mock_clob::{BookFrame, MockClob};
+use support::{fixture_crypto_market, market_fixture, test_config, wait_for};
+
+/// Three balanced updates per token to normalize the market, then YES drops to 0.40.
+fn arb_script(yes: &str, no: &str) -> Vec<BookFrame> {
+    let mut frames = Vec::new();
+    for _ in 0..3 {
+        frames.push(BookFrame::new(yes, vec![("0.49", "100")], vec![("0.50", "100")]));
+        frames.push(BookFrame::new(no, vec![("0.49", "100")], vec![("0.50", "100")]));
+    }
+    frames.push(BookFrame::new(yes, vec![("0.39", "100")], vec![("0.40", "100")]));
+    frames
+}
+
+struct Bot {
+    risk_manager: RiskManager,
+    execution_engine: Arc<ExecutionEngine>,
+    trades: broadcast::Receiver<TradeEvent>,
+}
+
+/// A user-channel trade event in which `order_id` took `size` as the taker.
+fn trade_event(trade_id: &str, order_id: &str, status: &str, size: &str, price: &str) -> WsTradeEvent {
+    serde_json::from_value(json!({
+        "id": trade_id,
+        "market": "",
+        "asset_id": "",
+        "status": status,
+        "price": price,
+        "size": size,
+        "taker_order_id": order_id,
+        "trader_side": "TAKER",
+    })).unwrap()
+}
+
+async fn start_bot(mock: &MockClob) -> Bot {
+    let mut config = test_config(&mock.http_url, &mock.ws_url);
+    config.rpc_url = Some(mock.http_url.clone()); // Any on-chain call shows up in the mock's request log
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let market_monitor = Arc::new(MarketMonitor::new(config.clone()));
+    let execution_engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), market_monitor.clone()));
+    let trades = execution_engine.trade_tx.subscribe();
+    let strategy_engine = StrategyEngine::new(market_monitor.clone(), execution_engine.clone(), config);
+
+    market_monitor.start_market_discovery().await;
+    tokio::spawn(async move { strategy_engine.run().await });
+    tokio::spawn(async move { market_monitor.run_ws_loop().await });
+
+    Bot { risk_manager, execution_engine, trades }
+}
+
+#[tokio::test]
+async fn detects_and_executes_arb_in_one_batch() {
+    let (_, yes, no) = fixture_crypto_market();
+    let mock = MockClob::start(&market_fixture(), arb_script(&yes, &no), Duration::from_millis(30)).await;
+    let mut bot = start_bot(&mock).await;
+
+    let expected_pnl = Decimal::new(1, 0); // (1 - 0.40 - 0.50) * 10
+    assert!(wait_for(Duration::from_secs(10), || bot.risk_manager.daily_pnl() == expected_pnl).await,
+        "pnl was {}", bot.risk_manager.daily_pnl());
+
+    assert_eq!(mock.batch_count(), 1);
+    let orders = mock.orders();
+    assert_eq!(orders.len(), 2);
+    let prices: Vec<&str> = orders.iter().map(|o| o["price"].as_str().unwrap()).collect();
+    assert!(prices.contains(&"0.40") && prices.contains(&"0.50"), "prices {:?}", prices);
+    assert!(orders.iter().all(|o| o["side"] == "BUY" && o["size"] == "10" && o["order_type"] == "FOK"));
+    assert!(!bot.risk_manager.is_safe_mode());
+
+    // Both legs sit in the ledger as 10 complete sets
+    let exposure = bot.risk_manager.positions().market_exposure(&yes, &no);
+    assert_eq!(exposure.net, Decimal::ZERO);
+    assert_eq!(exposure.locked_value, Decimal::from(10));
+    assert_eq!(exposure.cost_basis, Decimal::new(9, 0));
+    assert_eq!(bot.risk_manager.positions().position(&yes).unwrap().avg_cost, Decimal::new(40, 2));
+
+    let event = tokio::time::timeout(Duration::from_secs(5), bot.trades.recv()).await.unwrap().unwrap();
+    assert_eq!((event.status, event.filled_size, event.pnl), (TradeStatus::Filled, Decimal::from(10), expected_pnl));
+}
+
+#[tokio::test]
+async fn one_sided_fill_flattens_and_halts() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let mock = MockClob::start(&market_fixture(), arb_script(&yes, &no), Duration::from_millis(30)).await;
+    mock.set_fill_ratio(&no, Decimal::ZERO);
+    let mut bot = start_bot(&mock).await;
+
+    // NO never fills: the top-up fails, so the 10 YES are sold into the 0.39 bid
+    let expected_pnl = Decimal::new(-1, 1); // (0.39 - 0.40) * 10
+    assert!(wait_for(Duration::from_secs(10), || bot.risk_manager.daily_pnl() == expected_pnl).await,
+        "pnl was {}", bot.risk_manager.daily_pnl());
+
+    assert!(bot.risk_manager.is_safe_mode());
+    assert!(!bot.risk_manager.has_unhedged_exposure(&market_id));
+
+    // Resting orders are pulled before the rebalance, and nothing goes on-chain
+    let requests = mock.requests();
+    let cancel = requests.iter().position(|r| r == "DELETE /cancel-market-orders").expect("market cancel");
+    let rebalance = requests.iter().position(|r| r == "POST /order").expect("rebalance order");
+    assert!(cancel < rebalance, "{:?}", requests);
+    assert!(!requests.iter().any(|r| r == "POST /"), "{:?}", requests);
+
+    let orders = mock.orders();
+    let sells: Vec<_> = orders.iter().filter(|o| o["side"] == "SELL").collect();
+    assert_eq!(sells.len(), 1);
+    assert_eq!(sells[0]["token_id"], yes.as_str());
+    assert_eq!(sells[0]["price"], "0.39");
+    assert_eq!(sells[0]["order_type"], "FAK");
+
+    // Bought 10 YES at 0.40 and sold them at 0.39: flat, with the loss realized
+    let position = bot.risk_manager.positions().position(&yes).unwrap();
+    assert_eq!(position.quantity, Decimal::ZERO);
+    assert_eq!(position.realized_pnl, expected_pnl);
+    assert_eq!(bot.risk_manager.positions().market_exposure(&yes, &no).net, Decimal::ZERO);
+
+    // The trade's own result includes what its rebalance realized
+    let event = tokio::time::timeout(Duration::from_secs(5), bot.trades.recv()).await.unwrap().unwrap();
+    assert_eq!((event.status, event.pnl), (TradeStatus::PartialFillEmergency, expected_pnl));
+}
+
+#[tokio::test]
+async fn leg_imbalance_is_never_sold_at_a_loss() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let mut script = arb_script(&yes, &no);
+    // The only YES bid left is far below what YES cost
+    *script.last_mut().unwrap() = BookFrame::new(&yes, vec![("0.05", "100")], vec![("0.40", "100")]);
+    let mock = MockClob::start(&market_fixture(), script, Duration::from_millis(30)).await;
+    mock.set_fill_ratio(&no, Decimal::new(5, 1));
+    let mut bot = start_bot(&mock).await;
+
+    // 5 sets at 0.90 make 0.5; half the NO top-up matches at its 0.60 limit, breaking even
+    let event = tokio::time::timeout(Duration::from_secs(10), bot.trades.recv()).await.unwrap().unwrap();
+    assert_eq!((event.status, event.pnl), (TradeStatus::PartialFillEmergency, Decimal::new(5, 1)));
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::new(5, 1));
+
+    // The 2.5 YES left over are kept and flagged rather than dumped at 0.05
+    assert!(!mock.orders().iter().any(|o| o["side"] == "SELL"), "{:?}", mock.orders());
+    assert!(bot.risk_manager.has_unhedged_exposure(&market_id));
+    assert_eq!(bot.risk_manager.positions().position(&yes).unwrap().quantity, Decimal::from(10));
+}
+
+#[tokio::test]
+async fn failed_settlement_reverses_the_fill_and_its_profit() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let mock = MockClob::start(&market_fixture(), arb_script(&yes, &no), Duration::from_millis(30)).await;
+    let bot = start_bot(&mock).await;
+    assert!(wait_for(Duration::from_secs(10), || bot.risk_manager.daily_pnl() == Decimal::ONE).await,
+        "pnl was {}", bot.risk_manager.daily_pnl());
+
+    let orders = bot.execution_engine.orders();
+    let leg = |token: &str| orders.market_orders(&market_id).into_iter().find(|o| o.token_id == token).unwrap().order_id;
+    let (yes_order, no_order) = (leg(&yes), leg(&no));
+
+    // The NO leg settles; a late status for a confirmed trade is ignored
+    for status in ["MATCHED", "MINED", "CONFIRMED", "FAILED"] {
+        bot.execution_engine.on_trade_event(&trade_event("trade-no", &no_order, status, "10", "0.50"));
+    }
+    assert_eq!(orders.settlement("trade-no").unwrap().status, SettlementStatus::Confirmed);
+
+    // The YES leg matched, then failed on-chain after a retry
+    for status in ["MATCHED", "RETRYING"] {
+        bot.execution_engine.on_trade_event(&trade_event("trade-yes", &yes_order, status, "10", "0.40"));
+    }
+    assert_eq!(orders.unsettled().len(), 1);
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::ONE);
+    bot.execution_engine.on_trade_event(&trade_event("trade-yes", &yes_order, "FAILED", "10", "0.40"));
+
+    // The arb profit is gone, the YES shares were never ours and the NO leg is naked
+    assert_eq!(orders.settlement("trade-yes").unwrap().status, SettlementStatus::Failed);
+    assert!(orders.unsettled().is_empty());
+    assert_eq!(orders.get(&yes_order).unwrap().filled_size(), Decimal::ZERO);
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::ZERO);
+    assert_eq!(bot.risk_manager.positions().position(&yes).unwrap().quantity, Decimal::ZERO);
+    assert_eq!(bot.risk_manager.positions().market_exposure(&yes, &no).net, Decimal::from(-10));
+    assert!(bot.risk_manager.has_unhedged_exposure(&market_id));
+
+    // A repeated FAILED does not reverse twice
+    bot.execution_engine.on_trade_event(&trade_event("trade-yes", &yes_order, "FAILED", "10", "0.40"));
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::ZERO);
+}
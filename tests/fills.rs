@@ -0,0 +1,256 @@
+//! Fill verification: order responses, order lookups and trades.
+
+mod support;
+
+use chrono::{TimeZone, Utc};
+use polymarket_arb_bot::clock::ManualClock;
+use polymarket_arb_bot::execution::{correlate_batch, ExecutionEngine};
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::orders::OrderState;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::strategy::StrategyEngine;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse};
+use polymarket_arb_bot::types::{OrderRequest, OrderResponse, OrderType, Side, WsOrderEvent, WsTradeEvent};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use support::fakes::{ok_json, FakeHttp, FakeWs};
+use support::mock_clob::BookFrame;
+use support::{fixture_crypto_market, market_fixture, test_config};
+
+/// YES comes back "delayed" with no amounts; `yes_matched` of it matched at 0.39
+/// per the order lookup and its trade. NO matches in full in the response, unless
+/// `lose_no` drops its response from the batch.
+fn delayed_yes(yes: String, lose_no: bool, yes_matched: &'static str) -> impl Fn(&HttpRequest) -> HttpResponse + Send + Sync {
+    move |req: &HttpRequest| {
+        if req.url.contains("/markets") {
+            return ok_json(serde_json::from_str(&std::fs::read_to_string(market_fixture()).unwrap()).unwrap());
+        }
+        if req.method == Method::POST && req.url.ends_with("/orders") {
+            let legs: Vec<Value> = serde_json::from_str(req.body.as_deref().unwrap()).unwrap();
+            return ok_json(Value::Array(legs.iter().filter(|o| !lose_no || o["token_id"] == yes.as_str()).map(|o| {
+                if o["token_id"] == yes.as_str() {
+                    json!({ "success": true, "status": "delayed" })
+                } else {
+                    json!({ "success": true, "status": "matched", "makingAmount": "5", "takingAmount": "10" })
+                }
+            }).collect()));
+        }
+        // Trade ids double as order ids so the trade can name its taker order
+        if let Some(order_id) = req.url.split("/data/order/").nth(1) {
+            return ok_json(json!({ "status": "MATCHED", "size_matched": yes_matched, "associate_trades": [order_id] }));
+        }
+        if let Some(trade_id) = req.url.split("/data/trades?id=").nth(1) {
+            return ok_json(json!([{ "taker_order_id": trade_id, "price": "0.39", "size": yes_matched, "status": "MATCHED" }]));
+        }
+        HttpResponse { status: 404, body: String::new() }
+    }
+}
+
+struct Bot {
+    ws: Arc<FakeWs>,
+    http: Arc<FakeHttp>,
+    risk_manager: RiskManager,
+    engine: Arc<ExecutionEngine>,
+}
+
+/// Lookups find every order MATCHED in full at 0.39. A batch is answered with
+/// `batch_status`; single orders match in full at 0.45.
+fn batch_fails(batch_status: u16) -> impl Fn(&HttpRequest) -> HttpResponse + Send + Sync {
+    move |req: &HttpRequest| {
+        if req.url.contains("/markets") {
+            return ok_json(serde_json::from_str(&std::fs::read_to_string(market_fixture()).unwrap()).unwrap());
+        }
+        if req.method == Method::POST && req.url.ends_with("/orders") {
+            return HttpResponse { status: batch_status, body: "batch failed".to_string() };
+        }
+        if req.method == Method::POST && req.url.ends_with("/order") {
+            return ok_json(json!({ "success": true, "status": "matched", "makingAmount": "4.5", "takingAmount": "10" }));
+        }
+        if let Some(order_id) = req.url.split("/data/order/").nth(1) {
+            return ok_json(json!({ "status": "MATCHED", "size_matched": "10", "associate_trades": [order_id] }));
+        }
+        if let Some(trade_id) = req.url.split("/data/trades?id=").nth(1) {
+            return ok_json(json!([{ "taker_order_id": trade_id, "price": "0.39", "size": "10", "status": "MATCHED" }]));
+        }
+        HttpResponse { status: 404, body: String::new() }
+    }
+}
+
+async fn start_bot(http: FakeHttp, arb_order_type: OrderType) -> Bot {
+    let ws = Arc::new(FakeWs::new(0));
+    let http = Arc::new(http);
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let mut config = test_config("http://fake", "ws://fake");
+    config.arb_order_type = arb_order_type;
+
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), clock, http.clone(), ws.clone()));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), monitor.clone()));
+    let strategy = StrategyEngine::new(monitor.clone(), engine.clone(), config);
+
+    monitor.start_market_discovery().await;
+    tokio::spawn(async move { strategy.run().await });
+    let m = monitor.clone();
+    tokio::spawn(async move { m.run_ws_loop().await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    Bot { ws, http, risk_manager, engine }
+}
+
+/// Normalizes both books, then opens a 0.10 gap on YES.
+fn push_arb(ws: &FakeWs, yes: &str, no: &str) {
+    for _ in 0..3 {
+        ws.push(BookFrame::new(yes, vec![("0.49", "100")], vec![("0.50", "100")]).to_ws_json());
+        ws.push(BookFrame::new(no, vec![("0.49", "100")], vec![("0.50", "100")]).to_ws_json());
+    }
+    ws.push(BookFrame::new(yes, vec![("0.39", "100")], vec![("0.40", "100")]).to_ws_json());
+}
+
+#[tokio::test(start_paused = true)]
+async fn delayed_order_is_verified_from_its_lookup_and_trades() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let bot = start_bot(FakeHttp::new(delayed_yes(yes.clone(), false, "10")), OrderType::Fok).await;
+    push_arb(&bot.ws, &yes, &no);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    // Booked at the trade's 0.39, not the 0.40 limit
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::new(11, 1));
+    assert_eq!(bot.risk_manager.positions().position(&yes).unwrap().avg_cost, Decimal::new(39, 2));
+    assert!(bot.http.requests().iter().any(|r| r.url.contains("/data/trades?id=")));
+
+    let order = bot.engine.orders().market_orders(&market_id).into_iter().find(|o| o.token_id == yes).unwrap();
+    assert_eq!(order.fill_report().filled_size, Decimal::from(10));
+    assert_eq!(order.trade_ids.len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn leg_missing_from_the_batch_response_is_looked_up() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let bot = start_bot(FakeHttp::new(delayed_yes(yes.clone(), true, "10")), OrderType::Fok).await;
+    push_arb(&bot.ws, &yes, &no);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    // NO is neither rejected nor handed YES's response: its lookup finds the match at 0.39
+    let order = bot.engine.orders().market_orders(&market_id).into_iter().find(|o| o.token_id == no).unwrap();
+    assert!(bot.http.requests().iter().any(|r| r.url.ends_with(&format!("/data/order/{}", order.order_id))));
+    assert_eq!(order.fill_report().filled_size, Decimal::from(10));
+    assert_eq!(bot.risk_manager.positions().position(&no).unwrap().avg_cost, Decimal::new(39, 2));
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::new(22, 1));
+}
+
+#[tokio::test(start_paused = true)]
+async fn partly_matched_fak_leg_is_done_once_rest_says_matched() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let bot = start_bot(FakeHttp::new(delayed_yes(yes.clone(), false, "6")), OrderType::Fak).await;
+    push_arb(&bot.ws, &yes, &no);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    // The exchange killed the other 4: one lookup settles it, and nothing is cancelled.
+    // The rebalance's own YES buy goes in at a higher limit.
+    let order = bot.engine.orders().market_orders(&market_id).into_iter()
+        .find(|o| o.token_id == yes && o.price == Decimal::new(40, 2))
+        .unwrap();
+    assert_eq!(order.state, OrderState::Cancelled);
+    assert_eq!(order.fill_report().filled_size, Decimal::from(6));
+    let requests = bot.http.requests();
+    assert_eq!(requests.iter().filter(|r| r.url.contains("/data/order/")).count(), 1);
+    assert!(!requests.iter().any(|r| r.method == Method::DELETE), "{:?}", requests.iter().map(|r| &r.url).collect::<Vec<_>>());
+}
+
+#[tokio::test(start_paused = true)]
+async fn batch_that_may_have_landed_is_looked_up_not_reposted() {
+    let (_, yes, no) = fixture_crypto_market();
+    let singles = |bot: &Bot| bot.http.requests().iter().filter(|r| r.method == Method::POST && r.url.ends_with("/order")).count();
+
+    // A 503 can come after the exchange took the orders: each leg is looked up instead
+    let bot = start_bot(FakeHttp::new(batch_fails(503)), OrderType::Fok).await;
+    push_arb(&bot.ws, &yes, &no);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert_eq!(singles(&bot), 0);
+    assert_eq!(bot.http.requests().iter().filter(|r| r.url.contains("/data/order/")).count(), 2);
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::new(22, 1)); // (1 - 0.39 - 0.39) * 10
+
+    // A 400 was refused outright, so the legs go again one by one
+    let bot = start_bot(FakeHttp::new(batch_fails(400)), OrderType::Fok).await;
+    push_arb(&bot.ws, &yes, &no);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert_eq!(singles(&bot), 2);
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::ONE); // (1 - 0.45 - 0.45) * 10
+}
+
+#[test]
+fn batch_responses_are_matched_by_id_then_unused_position() {
+    let resp = |id: &str, status: &str| OrderResponse { success: true, order_id: id.to_string(), status: status.to_string(), ..Default::default() };
+    let statuses = |matched: Vec<Option<OrderResponse>>| -> Vec<Option<String>> {
+        matched.into_iter().map(|r| r.map(|r| r.status)).collect()
+    };
+
+    // Out of order, with one id missing: the id-less response takes its own position
+    let matched = correlate_batch(&["a", "b", "c"], vec![resp("b", "b"), resp("a", "a"), resp("", "c")]);
+    assert_eq!(statuses(matched), [Some("a".into()), Some("b".into()), Some("c".into())]);
+
+    // A response already claimed by id is not handed to the leg at its position
+    let matched = correlate_batch(&["a", "b", "c"], vec![resp("", "x"), resp("a", "a")]);
+    assert_eq!(statuses(matched), [Some("a".into()), None, None]);
+
+    // One naming an exchange id none of ours match still goes by position
+    let matched = correlate_batch(&["a", "b"], vec![resp("a", "a"), resp("z", "z")]);
+    assert_eq!(statuses(matched), [Some("a".into()), Some("z".into())]);
+
+    // A second response for an order already answered does not
+    let matched = correlate_batch(&["a", "b"], vec![resp("a", "a"), resp("a", "again")]);
+    assert_eq!(statuses(matched), [Some("a".into()), None]);
+}
+
+#[tokio::test]
+async fn fills_after_verification_are_booked_as_they_arrive() {
+    let (market_id, yes, _) = fixture_crypto_market();
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    let config = test_config("http://fake", "ws://fake");
+    let http = Arc::new(FakeHttp::new(delayed_yes(yes.clone(), false, "10")));
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), clock, http, Arc::new(FakeWs::new(0))));
+    monitor.start_market_discovery().await;
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = ExecutionEngine::new(config, risk_manager.clone(), monitor);
+
+    let req = OrderRequest {
+        market_id: market_id.clone(),
+        token_id: yes.clone(),
+        side: Side::Buy,
+        price: Decimal::new(40, 2),
+        size: Decimal::from(10),
+        order_type: OrderType::Gtc,
+        salt: 1,
+        nonce: 0,
+    };
+    engine.orders().create("0xlate", &req);
+    engine.orders().transition("0xlate", OrderState::Signed).unwrap();
+    engine.orders().transition("0xlate", OrderState::Submitted).unwrap();
+    let trade = |status: &str| -> WsTradeEvent {
+        serde_json::from_value(json!({ "id": "t1", "market": market_id, "asset_id": yes, "status": status,
+            "price": "0.39", "size": "4", "taker_order_id": "0xlate", "trader_side": "TAKER" })).unwrap()
+    };
+    let position = || risk_manager.positions().position(&yes).unwrap_or_default();
+
+    // While the fill is being verified, verification books it
+    engine.on_trade_event(&trade("MATCHED"));
+    assert_eq!(position().quantity, Decimal::ZERO);
+
+    // Verified with nothing filled (say its cancel lost the race), then the fill lands
+    engine.orders().record_booked_fill("0xlate", Decimal::ZERO).unwrap();
+    engine.on_trade_event(&trade("MATCHED"));
+    assert_eq!((position().quantity, position().avg_cost), (Decimal::from(4), Decimal::new(39, 2)));
+    assert!(risk_manager.has_unhedged_exposure(&market_id));
+
+    // Repeats are booked once; an order update books only what is new
+    engine.on_trade_event(&trade("MINED"));
+    let update: WsOrderEvent = serde_json::from_value(json!({ "id": "0xlate", "market": market_id, "asset_id": yes,
+        "type": "UPDATE", "price": "0.40", "original_size": "10", "size_matched": "6" })).unwrap();
+    engine.on_order_event(&update);
+    engine.on_order_event(&update);
+    assert_eq!(position().quantity, Decimal::from(6));
+    assert_eq!(engine.orders().get("0xlate").unwrap().booked_size, Decimal::from(6));
+}
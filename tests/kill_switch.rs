@@ -0,0 +1,148 @@
+//! The kill switch: control command, sentinel file and SIGUSR1.
+
+mod support;
+
+use polymarket_arb_bot::clock::SystemClock;
+use polymarket_arb_bot::config::Config;
+use polymarket_arb_bot::control;
+use polymarket_arb_bot::execution::ExecutionEngine;
+use polymarket_arb_bot::kill_switch::KillSwitch;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::safe_mode::SafeModeReason;
+use polymarket_arb_bot::strategy::StrategyEngine;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+use support::fakes::{ok_json, FakeHttp, FakeWs};
+use support::mock_clob::BookFrame;
+use support::{fixture_crypto_market, market_fixture, test_config, wait_for};
+
+fn markets_only(req: &HttpRequest) -> HttpResponse {
+    if req.url.contains("/markets") {
+        ok_json(serde_json::from_str(&std::fs::read_to_string(market_fixture()).unwrap()).unwrap())
+    } else {
+        HttpResponse { status: 404, body: String::new() }
+    }
+}
+
+fn push_arb(ws: &FakeWs, yes: &str, no: &str) {
+    for _ in 0..3 {
+        ws.push(BookFrame::new(yes, vec![("0.49", "100")], vec![("0.50", "100")]).to_ws_json());
+        ws.push(BookFrame::new(no, vec![("0.49", "100")], vec![("0.50", "100")]).to_ws_json());
+    }
+    ws.push(BookFrame::new(yes, vec![("0.39", "100")], vec![("0.40", "100")]).to_ws_json());
+}
+
+struct Bot {
+    ws: Arc<FakeWs>,
+    risk_manager: RiskManager,
+    kill_switch: Arc<KillSwitch>,
+}
+
+/// A paper-trading bot on the fixture market, streaming from a fake feed.
+async fn start_bot(config: Config) -> Bot {
+    let ws = Arc::new(FakeWs::new(0));
+    let http = Arc::new(FakeHttp::new(markets_only));
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), Arc::new(SystemClock), http, ws.clone()));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), monitor.clone()));
+    let kill_switch = Arc::new(KillSwitch::new(&config, risk_manager.clone(), engine.clone()));
+    let strategy = StrategyEngine::new(monitor.clone(), engine, config);
+
+    monitor.start_market_discovery().await;
+    tokio::spawn(async move { strategy.run().await });
+    tokio::spawn(async move { monitor.run_ws_loop().await });
+    assert!(wait_for(Duration::from_secs(5), || ws.is_connected()).await);
+
+    Bot { ws, risk_manager, kill_switch }
+}
+
+fn paper_config() -> Config {
+    let mut config = test_config("http://fake", "ws://fake");
+    config.paper_trading = true;
+    config
+}
+
+#[tokio::test]
+async fn kill_command_halts_and_flattens_while_data_keeps_flowing() {
+    let (_, yes, no) = fixture_crypto_market();
+    let bot = start_bot(paper_config()).await;
+    push_arb(&bot.ws, &yes, &no);
+    assert!(wait_for(Duration::from_secs(5), || bot.risk_manager.daily_pnl() == Decimal::ONE).await);
+
+    assert_eq!(control::kill(None, "kill").await, "ERR kill switch not available\n");
+    assert_eq!(control::kill(Some(&bot.kill_switch), "kill now").await, "ERR usage: kill [flatten]\n");
+    let reply = control::kill(Some(&bot.kill_switch), "kill flatten").await;
+    assert_eq!(reply, "OK killed: cancelled 0, flattened\n");
+    assert_eq!(bot.risk_manager.safe_mode().unwrap().reason, SafeModeReason::KillSwitch);
+
+    // The 10 sets went into the 0.39 and 0.49 bids: 0.88 for what was booked at 1
+    let ledger = bot.risk_manager.positions();
+    assert_eq!(ledger.position(&yes).unwrap().quantity, Decimal::ZERO);
+    assert_eq!(ledger.position(&no).unwrap().quantity, Decimal::ZERO);
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::new(-2, 1));
+
+    // Still streaming, but not trading
+    push_arb(&bot.ws, &yes, &no);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(bot.ws.is_connected());
+    assert_eq!(bot.risk_manager.daily_pnl(), Decimal::new(-2, 1));
+
+    assert_eq!(control::execute(&bot.risk_manager, "resume kill_switch").lines().last(), Some("OK cleared 1"));
+    assert!(!bot.risk_manager.is_safe_mode());
+}
+
+#[tokio::test]
+async fn flattening_clears_reported_exposure_and_the_market_trades_again() {
+    let (market_id, yes, no) = fixture_crypto_market();
+    let mut config = paper_config();
+    config.trade_cooldown_ms = 0;
+    let bot = start_bot(config).await;
+    push_arb(&bot.ws, &yes, &no);
+    assert!(wait_for(Duration::from_secs(5), || bot.risk_manager.daily_pnl() == Decimal::ONE).await);
+
+    // e.g. the NO leg failed on-chain: the YES shares are naked and the market is blocked
+    bot.risk_manager.report_exposure(&market_id, &yes, Decimal::from(10));
+    assert!(bot.risk_manager.has_unhedged_exposure(&market_id));
+
+    bot.kill_switch.trigger("test", true).await;
+    assert!(!bot.risk_manager.has_unhedged_exposure(&market_id));
+    let pnl = bot.risk_manager.daily_pnl();
+
+    bot.risk_manager.recover(Some(SafeModeReason::KillSwitch), None);
+    push_arb(&bot.ws, &yes, &no);
+    assert!(wait_for(Duration::from_secs(5), || bot.risk_manager.daily_pnl() == pnl + Decimal::ONE).await,
+        "pnl was {}", bot.risk_manager.daily_pnl());
+}
+
+#[tokio::test]
+async fn sentinel_file_and_sigusr1_trigger_the_kill_switch() {
+    let sentinel = std::env::temp_dir().join(format!("kill-{}", uuid::Uuid::new_v4()));
+    let mut config = paper_config();
+    config.kill_switch_file = Some(sentinel.display().to_string());
+    let bot = start_bot(config).await;
+    let kill_switch = bot.kill_switch.clone();
+    tokio::spawn(async move { kill_switch.run().await });
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(!bot.risk_manager.is_safe_mode());
+
+    std::fs::write(&sentinel, "").unwrap();
+    assert!(wait_for(Duration::from_secs(3), || bot.risk_manager.is_safe_mode()).await);
+    assert!(bot.risk_manager.safe_mode().unwrap().detail.contains("sentinel file"));
+    std::fs::remove_file(&sentinel).unwrap();
+    bot.risk_manager.recover(Some(SafeModeReason::KillSwitch), None);
+
+    #[cfg(unix)]
+    {
+        let status = std::process::Command::new("kill")
+            .args(["-USR1", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(wait_for(Duration::from_secs(3), || bot.risk_manager.is_safe_mode()).await);
+        assert_eq!(bot.risk_manager.safe_mode().unwrap().detail, "Kill switch via SIGUSR1");
+    }
+}
@@ -0,0 +1,49 @@
+//! Order salts and the exchange nonce.
+
+use polymarket_arb_bot::nonce::NonceManager;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn salts_are_unique_nonzero_and_json_safe() {
+    let nonces = NonceManager::new(0);
+    let salts: Vec<u64> = (0..20_000).map(|_| nonces.next_salt()).collect();
+    assert_eq!(salts.iter().collect::<HashSet<_>>().len(), salts.len());
+    assert!(salts.iter().all(|s| *s != 0 && *s < 1 << 53));
+}
+
+#[test]
+fn salts_stay_unique_across_threads() {
+    let nonces = Arc::new(NonceManager::new(0));
+    let handles: Vec<_> = (0..4).map(|_| {
+        let nonces = nonces.clone();
+        thread::spawn(move || (0..1000).map(|_| nonces.next_salt()).collect::<Vec<_>>())
+    }).collect();
+    let salts: Vec<u64> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+    assert_eq!(salts.iter().collect::<HashSet<_>>().len(), 4000);
+}
+
+#[test]
+fn bumps_follow_the_synced_exchange_nonce() {
+    let nonces = Arc::new(NonceManager::new(0));
+    assert_eq!(nonces.current_nonce(), 0);
+
+    nonces.set_nonce(7);
+    assert_eq!(nonces.current_nonce(), 7);
+    assert_eq!(nonces.bump_nonce(), 8);
+    assert_eq!(nonces.current_nonce(), 8);
+
+    // Concurrent bumps each get their own value
+    let handles: Vec<_> = (0..8).map(|_| {
+        let nonces = nonces.clone();
+        thread::spawn(move || nonces.bump_nonce())
+    }).collect();
+    let bumped: HashSet<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(bumped, (9..=16).collect());
+    assert_eq!(nonces.current_nonce(), 16);
+
+    // A re-sync from the chain wins over local bumps
+    nonces.set_nonce(3);
+    assert_eq!(nonces.current_nonce(), 3);
+}
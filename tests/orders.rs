@@ -0,0 +1,222 @@
+//! The order state machine and the registry built on it.
+
+use chrono::{Duration, TimeZone, Utc};
+use polymarket_arb_bot::clock::{Clock, ManualClock};
+use polymarket_arb_bot::orders::{OrderRegistry, OrderState, SettlementStatus, RETENTION_SECS};
+use polymarket_arb_bot::types::{OrderRequest, OrderType, Side};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn request(order_type: OrderType) -> OrderRequest {
+    OrderRequest {
+        market_id: "m1".to_string(),
+        token_id: "yes-1".to_string(),
+        side: Side::Buy,
+        price: dec("0.40"),
+        size: dec("10"),
+        order_type,
+        salt: 1,
+        nonce: 0,
+    }
+}
+
+fn registry() -> (Arc<ManualClock>, OrderRegistry) {
+    let clock = Arc::new(ManualClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap()));
+    (clock.clone(), OrderRegistry::with_clock(clock))
+}
+
+/// An order as it stands once the exchange has accepted it.
+fn submitted(registry: &OrderRegistry, order_id: &str, order_type: OrderType) {
+    registry.create(order_id, &request(order_type));
+    registry.transition(order_id, OrderState::Signed).unwrap();
+    registry.transition(order_id, OrderState::Submitted).unwrap();
+}
+
+#[test]
+fn transitions_follow_the_lifecycle() {
+    use OrderState::*;
+    let all = [Created, Signed, Submitted, Live, PartiallyFilled, Filled, Cancelled, Rejected, Expired];
+
+    assert!(Created.can_transition_to(Signed));
+    assert!(Signed.can_transition_to(Submitted));
+    assert!(Submitted.can_transition_to(Live));
+    assert!(Live.can_transition_to(PartiallyFilled));
+    assert!(PartiallyFilled.can_transition_to(PartiallyFilled));
+    assert!(PartiallyFilled.can_transition_to(Filled));
+
+    // No skipping signing or submission, and no going back
+    assert!(!Created.can_transition_to(Submitted));
+    assert!(!Signed.can_transition_to(Live));
+    assert!(!Live.can_transition_to(Submitted));
+    assert!(!PartiallyFilled.can_transition_to(Live));
+    assert!(!Live.can_transition_to(Rejected));
+
+    // Nothing leaves a terminal state
+    for state in all.iter().filter(|s| s.is_terminal()) {
+        assert!(all.iter().all(|next| !state.can_transition_to(*next)), "{} has a way out", state);
+    }
+}
+
+#[test]
+fn registry_refuses_illegal_and_duplicate_updates() {
+    let (_, registry) = registry();
+    registry.create("a", &request(OrderType::Gtc));
+    assert!(registry.transition("a", OrderState::Live).is_err());
+    assert!(registry.transition("missing", OrderState::Signed).is_err());
+
+    registry.transition("a", OrderState::Signed).unwrap();
+    registry.transition("a", OrderState::Submitted).unwrap();
+    registry.transition("a", OrderState::Live).unwrap();
+    // A PLACEMENT repeated on reconnect changes nothing
+    assert!(registry.transition("a", OrderState::Live).is_err());
+    assert_eq!(registry.get("a").unwrap().state, OrderState::Live);
+
+    // A trade seen at MATCHED and again at MINED counts once
+    registry.record_trade("a", "t1", dec("4"), dec("0.40")).unwrap();
+    registry.record_trade("a", "t1", dec("4"), dec("0.40")).unwrap();
+    let order = registry.get("a").unwrap();
+    assert_eq!(order.state, OrderState::PartiallyFilled);
+    assert_eq!(order.filled_size(), dec("4"));
+
+    registry.record_trade("a", "t2", dec("6"), dec("0.39")).unwrap();
+    assert_eq!(registry.get("a").unwrap().state, OrderState::Filled);
+    // A cancellation arriving after the fill is refused
+    assert!(registry.transition("a", OrderState::Cancelled).is_err());
+    assert!(registry.reject("a", "late".to_string()).is_err());
+    assert_eq!(registry.get("a").unwrap().state, OrderState::Filled);
+
+    // Settlement: repeats are ignored, going backwards is refused
+    assert!(registry.settle("t1", SettlementStatus::Mined).unwrap().is_none());
+    assert!(registry.settle("t1", SettlementStatus::Mined).unwrap().is_none());
+    assert!(registry.settle("t1", SettlementStatus::Matched).is_err());
+    assert!(registry.settle("t1", SettlementStatus::Confirmed).unwrap().is_none());
+    assert!(registry.settle("t1", SettlementStatus::Failed).is_err());
+    assert!(registry.settle("t9", SettlementStatus::Mined).is_err());
+}
+
+#[test]
+fn only_open_gtd_orders_expire() {
+    let (clock, registry) = registry();
+    let expiration = clock.now() + Duration::seconds(120);
+    submitted(&registry, "gtd", OrderType::Gtd { expiration });
+    submitted(&registry, "filled", OrderType::Gtd { expiration });
+    registry.record_matched("filled", dec("10"), None).unwrap();
+    submitted(&registry, "gtc", OrderType::Gtc);
+    registry.create("unsigned", &request(OrderType::Gtd { expiration }));
+
+    assert!(registry.expire_due(expiration - Duration::seconds(1)).is_empty());
+    assert_eq!(registry.expire_due(expiration), vec!["gtd".to_string()]);
+    assert!(registry.expire_due(expiration).is_empty());
+
+    assert_eq!(registry.get("gtd").unwrap().state, OrderState::Expired);
+    assert_eq!(registry.get("gtd").unwrap().updated_at, expiration);
+    assert_eq!(registry.get("filled").unwrap().state, OrderState::Filled);
+    assert_eq!(registry.get("gtc").unwrap().state, OrderState::Submitted);
+    assert_eq!(registry.get("unsigned").unwrap().state, OrderState::Created);
+    assert!(registry.transition("gtd", OrderState::Live).is_err());
+}
+
+#[test]
+fn finished_orders_are_pruned_after_the_retention_window() {
+    let (clock, registry) = registry();
+    submitted(&registry, "open", OrderType::Gtc);
+    submitted(&registry, "cancelled", OrderType::Gtc);
+    registry.transition("cancelled", OrderState::Cancelled).unwrap();
+    submitted(&registry, "settling", OrderType::Gtc);
+    registry.record_trade("settling", "t1", dec("10"), dec("0.40")).unwrap();
+
+    let retention = Duration::seconds(RETENTION_SECS);
+    assert_eq!(registry.prune(clock.now() + retention), 0);
+
+    clock.advance(retention + Duration::seconds(1));
+    assert_eq!(registry.prune(clock.now()), 1);
+    assert!(registry.get("cancelled").is_none());
+    assert!(registry.get("open").is_some());
+    // Kept while its trade can still fail on-chain
+    assert!(registry.get("settling").is_some());
+
+    registry.settle("t1", SettlementStatus::Confirmed).unwrap();
+    clock.advance(retention + Duration::seconds(1));
+    assert_eq!(registry.prune(clock.now()), 1);
+    assert!(registry.get("settling").is_none());
+    assert!(registry.settlement("t1").is_none());
+}
+
+#[test]
+fn settled_trades_are_pruned_after_the_retention_window() {
+    let (clock, registry) = registry();
+    submitted(&registry, "a", OrderType::Gtc);
+    registry.record_trade("a", "confirmed", dec("2"), dec("0.40")).unwrap();
+    registry.record_trade("a", "failed", dec("2"), dec("0.40")).unwrap();
+    registry.record_trade("a", "mined", dec("2"), dec("0.40")).unwrap();
+    registry.settle("confirmed", SettlementStatus::Confirmed).unwrap();
+    registry.settle("failed", SettlementStatus::Failed).unwrap();
+    registry.settle("mined", SettlementStatus::Mined).unwrap();
+
+    clock.advance(Duration::seconds(RETENTION_SECS + 1));
+    registry.prune(clock.now());
+    assert!(registry.settlement("confirmed").is_none());
+    assert!(registry.settlement("failed").is_none());
+    // Still waiting on the chain, and so is its order
+    assert_eq!(registry.settlement("mined").unwrap().status, SettlementStatus::Mined);
+    assert_eq!(registry.get("a").unwrap().failed_size, dec("2"));
+}
+
+#[test]
+fn trades_and_settlements_can_arrive_together() {
+    let (_, registry) = registry();
+    let registry = Arc::new(registry);
+    let n = 200;
+    for i in 0..n {
+        submitted(&registry, &format!("o{}", i), OrderType::Gtc);
+        registry.record_trade(&format!("o{}", i), &format!("t{}", i), dec("1"), dec("0.40")).unwrap();
+    }
+
+    // One thread fills while the other fails the trades the fills settle against
+    let (done, finished) = std::sync::mpsc::channel();
+    let trades = {
+        let (registry, done) = (registry.clone(), done.clone());
+        std::thread::spawn(move || {
+            for i in 0..n {
+                registry.record_trade(&format!("o{}", i), &format!("u{}", i), dec("1"), dec("0.40")).unwrap();
+            }
+            done.send(()).unwrap();
+        })
+    };
+    let settles = {
+        let registry = registry.clone();
+        std::thread::spawn(move || {
+            for i in 0..n {
+                registry.settle(&format!("t{}", i), SettlementStatus::Failed).unwrap();
+            }
+            done.send(()).unwrap();
+        })
+    };
+    for _ in 0..2 {
+        finished.recv_timeout(std::time::Duration::from_secs(10)).expect("registry deadlocked");
+    }
+    trades.join().unwrap();
+    settles.join().unwrap();
+
+    for i in 0..n {
+        let order = registry.get(&format!("o{}", i)).unwrap();
+        assert_eq!((order.traded_size, order.failed_size), (dec("2"), dec("1")));
+    }
+}
+
+#[test]
+fn order_types_map_to_the_clob() {
+    let expiration = Utc.timestamp_opt(1_700_000_120, 0).unwrap();
+    let types = [OrderType::Gtc, OrderType::Gtd { expiration }, OrderType::Fok, OrderType::Fak];
+
+    let names: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+    assert_eq!(names, ["GTC", "GTD", "FOK", "FAK"]);
+    let expirations: Vec<u64> = types.iter().map(|t| t.expiration_secs()).collect();
+    assert_eq!(expirations, [0, 1_700_000_120, 0, 0]);
+    let immediate: Vec<bool> = types.iter().map(|t| t.is_immediate()).collect();
+    assert_eq!(immediate, [false, false, true, true]);
+}
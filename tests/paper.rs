@@ -0,0 +1,131 @@
+//! The paper matcher: depth, queue share, slippage, order types and cancels.
+
+mod support;
+
+use chrono::{Duration, TimeZone, Utc};
+use polymarket_arb_bot::capture::CaptureWriter;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::orders::RETENTION_SECS;
+use polymarket_arb_bot::paper::PaperMatcher;
+use polymarket_arb_bot::types::{CancelScope, OrderRequest, OrderType, Side};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::sync::Arc;
+use support::{fixture_crypto_market, market_fixture, test_config};
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+/// YES asks 5 @ 0.40 and 10 @ 0.41, bids 10 @ 0.38 and 10 @ 0.37.
+async fn monitor() -> Arc<MarketMonitor> {
+    let (_, yes, _) = fixture_crypto_market();
+    let dir = std::env::temp_dir().join(format!("paper-test-{}", uuid::Uuid::new_v4()));
+    let start = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    {
+        let mut writer = CaptureWriter::new(&dir, 3600).unwrap();
+        writer.write_markets(start, &std::fs::read_to_string(market_fixture()).unwrap()).unwrap();
+        writer.write_frame(start, &json!({
+            "event_type": "book",
+            "asset_id": yes,
+            "bids": [["0.37", "10"], ["0.38", "10"]],
+            "asks": [["0.41", "10"], ["0.40", "5"]],
+            "hash": "",
+            "timestamp": "1700000000000",
+        }).to_string()).unwrap();
+    }
+    let monitor = Arc::new(MarketMonitor::new(test_config("http://unused", "ws://unused")));
+    monitor.replay(&dir, 0.0).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    monitor
+}
+
+fn order(side: Side, price: &str, size: &str, order_type: OrderType) -> OrderRequest {
+    let (market_id, yes, _) = fixture_crypto_market();
+    OrderRequest { market_id, token_id: yes, side, price: dec(price), size: dec(size), order_type, salt: 1, nonce: 0 }
+}
+
+#[tokio::test]
+async fn walks_the_book_within_the_limit() {
+    let paper = PaperMatcher::new(monitor().await, 0, Decimal::ZERO, Decimal::ONE);
+
+    // 5 @ 0.40 then 5 @ 0.41
+    let resp = paper.submit("buy", &order(Side::Buy, "0.41", "10", OrderType::Fak)).await;
+    assert_eq!((resp.status.as_str(), resp.taking_amount.as_str(), resp.making_amount.as_str()), ("matched", "10", "4.05"));
+
+    // Only the 0.40 level is inside the limit: FOK kills, FAK takes what is there
+    let resp = paper.submit("fok", &order(Side::Buy, "0.40", "10", OrderType::Fok)).await;
+    assert_eq!(resp.status, "unmatched");
+    assert!(resp.taking_amount.is_empty() && resp.making_amount.is_empty());
+    let resp = paper.submit("fak", &order(Side::Buy, "0.40", "10", OrderType::Fak)).await;
+    assert_eq!(resp.taking_amount, "5");
+    assert_eq!(paper.order("fak").unwrap().status, "MATCHED");
+
+    // Sells hit the best bid first; the USDC is what we take
+    let resp = paper.submit("sell", &order(Side::Sell, "0.37", "15", OrderType::Fak)).await;
+    assert_eq!((resp.making_amount.as_str(), resp.taking_amount.as_str()), ("15", "5.65"));
+
+    let mut other = order(Side::Buy, "0.50", "1", OrderType::Fak);
+    other.token_id = "unknown".to_string();
+    let resp = paper.submit("none", &other).await;
+    assert!(!resp.success);
+    assert!(resp.error_msg.contains("no book"));
+}
+
+#[tokio::test]
+async fn queue_share_and_slippage_worsen_fills() {
+    // Half of each level reaches us, and every fill is 100 bps worse, capped at the limit
+    let paper = PaperMatcher::new(monitor().await, 0, dec("100"), dec("0.5"));
+
+    let resp = paper.submit("fok", &order(Side::Buy, "0.41", "10", OrderType::Fok)).await;
+    assert_eq!(resp.status, "unmatched");
+
+    let resp = paper.submit("fak", &order(Side::Buy, "0.41", "10", OrderType::Fak)).await;
+    assert_eq!(resp.taking_amount, "7.5");
+    // 2.5 @ 0.404 + 5 @ 0.41 (0.4141 capped)
+    assert_eq!(dec(&resp.making_amount), dec("3.06"));
+}
+
+#[tokio::test]
+async fn resting_remainders_can_be_cancelled() {
+    let (market_id, yes, _) = fixture_crypto_market();
+    let paper = PaperMatcher::new(monitor().await, 0, Decimal::ZERO, Decimal::ONE);
+
+    let resp = paper.submit("gtc", &order(Side::Buy, "0.40", "10", OrderType::Gtc)).await;
+    assert_eq!((resp.status.as_str(), resp.taking_amount.as_str()), ("matched", "5"));
+    let resp = paper.submit("away", &order(Side::Buy, "0.30", "10", OrderType::Gtc)).await;
+    assert_eq!(resp.status, "live");
+    paper.submit("fak", &order(Side::Buy, "0.40", "1", OrderType::Fak)).await;
+
+    let lookup = paper.order("gtc").unwrap();
+    assert_eq!((lookup.status.as_str(), lookup.size_matched.as_str()), ("LIVE", "5"));
+
+    // A filled order is refused by id, and only live orders are cancelled by market
+    let resp = paper.cancel(&CancelScope::Orders(vec!["fak".to_string()]));
+    assert!(resp.canceled.is_empty());
+    assert!(resp.not_canceled.contains_key("fak"));
+    let resp = paper.cancel(&CancelScope::Market { market_id: market_id.clone(), asset_id: Some("other".to_string()) });
+    assert!(resp.canceled.is_empty());
+    let mut resp = paper.cancel(&CancelScope::Market { market_id, asset_id: Some(yes) });
+    resp.canceled.sort();
+    assert_eq!(resp.canceled, ["away", "gtc"]);
+    assert_eq!(paper.order("gtc").unwrap().status, "CANCELED");
+    assert!(paper.cancel(&CancelScope::All).canceled.is_empty());
+}
+
+#[tokio::test]
+async fn finished_orders_are_pruned_after_the_retention_window() {
+    let monitor = monitor().await;
+    let now = monitor.clock().now();
+    let paper = PaperMatcher::new(monitor, 0, Decimal::ZERO, Decimal::ONE);
+    paper.submit("filled", &order(Side::Buy, "0.41", "10", OrderType::Fak)).await;
+    paper.submit("killed", &order(Side::Buy, "0.30", "10", OrderType::Fak)).await;
+    paper.submit("resting", &order(Side::Buy, "0.30", "10", OrderType::Gtc)).await;
+
+    let retention = Duration::seconds(RETENTION_SECS);
+    assert_eq!(paper.prune(now + retention), 0);
+    assert_eq!(paper.prune(now + retention + Duration::seconds(1)), 2);
+    assert!(paper.order("filled").is_none() && paper.order("killed").is_none());
+    // Still on the book, however old
+    assert_eq!(paper.order("resting").unwrap().status, "LIVE");
+}
@@ -0,0 +1,85 @@
+//! Position ledger, mark-to-market, the equity-driven loss limit and exposure caps.
+
+use polymarket_arb_bot::risk::{ExposureLimits, RiskManager};
+use polymarket_arb_bot::types::Side;
+use rust_decimal::Decimal;
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn marks_unpaired_shares_and_values_sets_at_one() {
+    let risk_manager = RiskManager::new(Decimal::from(1000), dec("0.02"), dec("0.1"));
+    risk_manager.record_fill("m", "yes", &Side::Buy, dec("10"), dec("0.40"));
+    risk_manager.record_fill("m", "no", &Side::Buy, dec("10"), dec("0.50"));
+    risk_manager.record_fill("m", "yes", &Side::Buy, dec("5"), dec("0.45"));
+
+    let ledger = risk_manager.positions();
+    let yes = ledger.position("yes").unwrap();
+    assert_eq!(yes.quantity, dec("15"));
+    assert_eq!(yes.avg_cost.round_dp(6), dec("0.416667"));
+    let exposure = ledger.market_exposure("yes", "no");
+    assert_eq!((exposure.net, exposure.locked_value), (dec("5"), dec("10")));
+
+    // The 10 sets stay at 1 USDC however low the bids go; the extra 5 YES mark at 0.20
+    let valuation = ledger.mark(|token| match token {
+        "yes" => Some(dec("0.20")),
+        _ => Some(dec("0.01")),
+    });
+    assert_eq!(valuation.market_value, dec("11"));
+    assert_eq!(valuation.cost_basis, dec("11.25"));
+    assert_eq!(valuation.unrealized_pnl, dec("-0.25"));
+
+    // Selling realizes against the average cost; unpriced tokens keep their last mark
+    risk_manager.record_fill("m", "yes", &Side::Sell, dec("5"), dec("0.30"));
+    let valuation = ledger.mark(|_| None);
+    assert_eq!(valuation.realized_pnl.round_dp(6), dec("-0.583333"));
+    assert_eq!(valuation.market_value, dec("10"));
+}
+
+#[test]
+fn marked_losses_trip_the_breaker_before_any_booked_loss() {
+    // 2% of 100 USDC: trip below -2
+    let risk_manager = RiskManager::new(Decimal::from(100), dec("0.02"), dec("0.5"));
+    risk_manager.record_fill("m", "yes", &Side::Buy, dec("10"), dec("0.50"));
+
+    risk_manager.record_valuation(risk_manager.positions().mark(|_| Some(dec("0.40"))));
+    assert!(!risk_manager.is_safe_mode());
+    assert_eq!(risk_manager.equity(), dec("99"));
+
+    risk_manager.record_valuation(risk_manager.positions().mark(|_| Some(dec("0.25"))));
+    assert_eq!(risk_manager.daily_pnl(), Decimal::ZERO);
+    assert!(risk_manager.is_safe_mode());
+    assert!(!risk_manager.check_trade_size(Decimal::ONE));
+}
+
+#[test]
+fn exposure_caps_apply_per_market_tag_total_and_count() {
+    let limits = ExposureLimits {
+        max_market_pct: Some(dec("0.10")),
+        max_tag_pct: Some(dec("0.15")),
+        max_total_pct: Some(dec("0.25")),
+        max_open_positions: Some(3),
+    };
+    let risk_manager = RiskManager::new(Decimal::from(100), dec("0.02"), dec("0.5")).with_exposure_limits(limits);
+    let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let hold = |market: &str, tags: Vec<String>, cost: &str| {
+        assert!(risk_manager.check_exposure(market, &tags, dec(cost)), "{} {}", market, cost);
+        risk_manager.record_fill(market, &format!("{}-yes", market), &Side::Buy, dec(cost), Decimal::ONE);
+    };
+    hold("btc-1", tags(&["Crypto", "Bitcoin"]), "8");
+    hold("eth-1", tags(&["Crypto", "Ethereum"]), "6");
+
+    // 8 + 3 > 10 in the market
+    assert!(!risk_manager.check_exposure("btc-1", &tags(&["Crypto", "Bitcoin"]), dec("3")));
+    // 8 + 6 + 2 > 15 under Crypto
+    assert!(!risk_manager.check_exposure("sol-1", &tags(&["Crypto", "Solana"]), dec("2")));
+    assert!(risk_manager.check_exposure("fed-1", &tags(&["Economy"]), dec("2")));
+    // 14 + 9 + 3 > 25 in total
+    hold("election-1", tags(&["Politics"]), "9");
+    assert!(!risk_manager.check_exposure("fed-1", &tags(&["Economy"]), dec("3")));
+    // Three markets held: a fourth is refused even when small, adding to a held one is not
+    assert!(!risk_manager.check_exposure("fed-1", &tags(&["Economy"]), dec("1")));
+    assert!(risk_manager.check_exposure("election-1", &tags(&["Politics"]), dec("1")));
+}
@@ -0,0 +1,63 @@
+//! Capital reserved for trades in flight: reserve, commit on fill, release.
+
+use polymarket_arb_bot::risk::{ExposureLimits, RiskManager};
+use polymarket_arb_bot::types::{CollateralBalance, Side};
+use rust_decimal::Decimal;
+use std::thread;
+use uuid::Uuid;
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn funded(balance: &str) -> RiskManager {
+    let risk_manager = RiskManager::new(dec(balance), dec("0.02"), dec("0.5"));
+    risk_manager.set_starting_balance(CollateralBalance { balance: dec(balance), allowance: dec(balance) });
+    risk_manager
+}
+
+#[test]
+fn concurrent_trades_cannot_reserve_the_same_balance() {
+    let risk_manager = funded("100");
+
+    // Eight trades of 30 race for 100 USDC: only three fit
+    let ids: Vec<Uuid> = (0..8).map(|_| Uuid::new_v4()).collect();
+    let handles: Vec<_> = ids.iter().map(|id| {
+        let (risk_manager, id) = (risk_manager.clone(), *id);
+        thread::spawn(move || risk_manager.reserve(id, "m", &[], dec("30")).then_some(id))
+    }).collect();
+    let held: Vec<Uuid> = handles.into_iter().filter_map(|h| h.join().unwrap()).collect();
+    assert_eq!(held.len(), 3);
+    assert_eq!(risk_manager.reserved(), dec("90"));
+    assert_eq!(risk_manager.available_balance(), Some(dec("10")));
+    assert!(!risk_manager.check_trade_size(dec("11")));
+
+    // A fill spends cash and its hold together; a partial fill leaves the rest held
+    risk_manager.record_fill("m", "yes", &Side::Buy, dec("50"), dec("0.40"));
+    risk_manager.commit(held[0], dec("20"));
+    assert_eq!(risk_manager.available_balance(), Some(dec("10")));
+
+    // Releasing frees the unfilled remainder, once
+    assert_eq!(risk_manager.release(held[0]), dec("10"));
+    assert_eq!(risk_manager.release(held[0]), Decimal::ZERO);
+    assert_eq!(risk_manager.release(held[1]), dec("30"));
+    assert_eq!(risk_manager.available_balance(), Some(dec("50")));
+    assert!(risk_manager.reserve(Uuid::new_v4(), "m", &[], dec("50")));
+}
+
+#[test]
+fn holds_count_toward_exposure_caps() {
+    let limits = ExposureLimits { max_market_pct: Some(dec("0.10")), ..Default::default() };
+    let risk_manager = RiskManager::new(Decimal::from(1000), dec("0.02"), dec("0.5")).with_exposure_limits(limits);
+    let tags = vec!["Crypto".to_string()];
+
+    // 100 per market: a second 60 on top of a pending 60 is refused until the first is released
+    let first = Uuid::new_v4();
+    assert!(risk_manager.reserve(first, "btc-1", &tags, dec("60")));
+    assert!(!risk_manager.reserve(Uuid::new_v4(), "btc-1", &tags, dec("60")));
+    assert!(!risk_manager.check_exposure("btc-1", &tags, dec("50")));
+    assert!(risk_manager.reserve(Uuid::new_v4(), "eth-1", &tags, dec("60")));
+
+    risk_manager.release(first);
+    assert!(risk_manager.check_exposure("btc-1", &tags, dec("60")));
+}
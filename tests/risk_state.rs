@@ -0,0 +1,129 @@
+//! Trading-day rollover and risk state surviving a restart.
+
+use chrono::{FixedOffset, TimeZone, Utc};
+use polymarket_arb_bot::clock::ManualClock;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::safe_mode::SafeModeReason;
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn state_file() -> PathBuf {
+    std::env::temp_dir().join(format!("risk-state-{}.json", uuid::Uuid::new_v4()))
+}
+
+fn risk_manager(clock: &Arc<ManualClock>) -> RiskManager {
+    // 2% of 100 USDC: trip below -2
+    RiskManager::new(Decimal::from(100), Decimal::new(2, 2), Decimal::new(5, 1))
+        .with_clock(clock.clone())
+        .with_day_offset(FixedOffset::west_opt(5 * 3600).unwrap())
+}
+
+#[test]
+fn daily_pnl_rolls_over_at_the_configured_offset() {
+    // 23:00 UTC is 18:00 at UTC-5, so the day ends five hours after UTC midnight
+    let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 23, 0, 0).unwrap()));
+    let risk_manager = risk_manager(&clock);
+    risk_manager.record_pnl(Decimal::new(-15, 1));
+
+    clock.advance(chrono::Duration::hours(2));
+    assert_eq!(risk_manager.daily_pnl(), Decimal::new(-15, 1));
+
+    clock.advance(chrono::Duration::hours(4));
+    assert_eq!(risk_manager.daily_pnl(), Decimal::ZERO);
+    let history = risk_manager.pnl_history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].day.to_string(), "2024-03-01");
+    assert_eq!(history[0].pnl, Decimal::new(-15, 1));
+
+    // A fresh 2% of the new day's starting balance
+    risk_manager.record_pnl(Decimal::new(-15, 1));
+    assert!(!risk_manager.is_safe_mode());
+}
+
+#[test]
+fn tripped_breaker_survives_a_restart() {
+    let path = state_file();
+    let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap()));
+
+    let first = risk_manager(&clock).with_persistence(&path).unwrap();
+    first.record_pnl(Decimal::new(-25, 1));
+    assert!(first.is_safe_mode());
+    drop(first);
+
+    let restarted = risk_manager(&clock).with_persistence(&path).unwrap();
+    assert!(restarted.is_safe_mode());
+    assert!(*restarted.subscribe_safe_mode().borrow());
+    assert_eq!(restarted.safe_mode().unwrap().reason, SafeModeReason::DailyLoss);
+    assert_eq!(restarted.daily_pnl(), Decimal::new(-25, 1));
+    assert!(!restarted.check_trade_size(Decimal::ONE));
+    drop(restarted);
+
+    // The next day starts with a clean PnL, but without daily_loss in
+    // SAFE_MODE_AUTO_RECOVER (the default) the halt stays until an operator clears it
+    clock.advance(chrono::Duration::days(1));
+    let next_day = risk_manager(&clock).with_persistence(&path).unwrap();
+    assert_eq!(next_day.daily_pnl(), Decimal::ZERO);
+    assert_eq!(next_day.pnl_history()[0].pnl, Decimal::new(-25, 1));
+    assert!(next_day.is_safe_mode());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn daily_loss_halt_clears_the_next_day_when_opted_in() {
+    let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap()));
+    let risk_manager = risk_manager(&clock).with_auto_recover(vec![SafeModeReason::DailyLoss]);
+    risk_manager.record_pnl(Decimal::new(-25, 1));
+    assert!(risk_manager.is_safe_mode());
+
+    clock.advance(chrono::Duration::days(1));
+    assert_eq!(risk_manager.daily_pnl(), Decimal::ZERO);
+    assert!(!risk_manager.is_safe_mode());
+}
+
+#[test]
+fn unhedged_exposure_survives_a_restart_until_cleared() {
+    let path = state_file();
+    let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap()));
+
+    let first = risk_manager(&clock).with_persistence(&path).unwrap();
+    first.report_exposure("btc-1", "yes-1", Decimal::from(10));
+    first.flush();
+    drop(first);
+
+    let restarted = risk_manager(&clock).with_persistence(&path).unwrap();
+    assert!(restarted.has_unhedged_exposure("btc-1"));
+    restarted.clear_exposure("btc-1", "yes-1", Decimal::from(4));
+    assert!(restarted.has_unhedged_exposure("btc-1"));
+    restarted.clear_exposure("btc-1", "yes-1", Decimal::from(6));
+    assert!(!restarted.has_unhedged_exposure("btc-1"));
+    drop(restarted);
+
+    let cleared = risk_manager(&clock).with_persistence(&path).unwrap();
+    assert!(!cleared.has_unhedged_exposure("btc-1"));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn unchanged_state_is_not_rewritten() {
+    let path = state_file();
+    let clock = Arc::new(ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 15, 0, 0).unwrap()));
+    let risk_manager = risk_manager(&clock).with_persistence(&path).unwrap();
+    risk_manager.record_pnl(Decimal::ONE);
+    risk_manager.flush();
+
+    // Nothing persisted changes: the file is left alone, so removing it sticks
+    std::fs::remove_file(&path).unwrap();
+    risk_manager.record_pnl(Decimal::ZERO);
+    risk_manager.flush();
+    assert!(!path.exists());
+
+    risk_manager.record_pnl(Decimal::ONE);
+    risk_manager.flush();
+    let saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(saved["daily_pnl"], "2");
+
+    std::fs::remove_file(path).unwrap();
+}
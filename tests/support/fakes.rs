@@ -0,0 +1,110 @@
+//! In-memory `HttpTransport` and `WsConnector` for deterministic tests.
+
+use futures_util::future::BoxFuture;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse, HttpTransport, WsConnector, WsSink, WsSource};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::tungstenite::Error as WsError;
+
+type Handler = Box<dyn Fn(&HttpRequest) -> HttpResponse + Send + Sync>;
+
+/// Answers every request with `handler` and records it.
+pub struct FakeHttp {
+    handler: Handler,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl FakeHttp {
+    pub fn new(handler: impl Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static) -> Self {
+        Self { handler: Box::new(handler), requests: Mutex::new(Vec::new()) }
+    }
+
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl HttpTransport for FakeHttp {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, anyhow::Result<HttpResponse>> {
+        let response = (self.handler)(&request);
+        self.requests.lock().unwrap().push(request);
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+pub fn ok_json(body: serde_json::Value) -> HttpResponse {
+    HttpResponse { status: 200, body: body.to_string() }
+}
+
+/// Refuses the first `fail_first` connections, then hands out sessions whose
+/// incoming frames are pushed by the test.
+pub struct FakeWs {
+    fail_first: usize,
+    connects: Mutex<Vec<tokio::time::Instant>>,
+    session: Mutex<Option<mpsc::UnboundedSender<Message>>>,
+    sent: Arc<Mutex<Vec<Message>>>,
+}
+
+impl FakeWs {
+    pub fn new(fail_first: usize) -> Self {
+        Self {
+            fail_first,
+            connects: Mutex::new(Vec::new()),
+            session: Mutex::new(None),
+            sent: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// When each connection attempt was made, on the tokio clock.
+    pub fn connect_times(&self) -> Vec<tokio::time::Instant> {
+        self.connects.lock().unwrap().clone()
+    }
+
+    /// Delivers a text frame on the current session.
+    pub fn push(&self, text: String) {
+        let session = self.session.lock().unwrap();
+        session.as_ref().expect("no open session").send(Message::Text(text)).unwrap();
+    }
+
+    /// Ends the current session as if the server went away.
+    pub fn drop_session(&self) {
+        self.session.lock().unwrap().take();
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    /// Text frames the client has sent (subscriptions).
+    pub fn sent_text(&self) -> Vec<String> {
+        self.sent.lock().unwrap().iter()
+            .filter_map(|m| match m { Message::Text(t) => Some(t.clone()), _ => None })
+            .collect()
+    }
+}
+
+impl WsConnector for FakeWs {
+    fn connect(&self, _request: Request) -> BoxFuture<'_, Result<(WsSink, WsSource), WsError>> {
+        let attempt = {
+            let mut connects = self.connects.lock().unwrap();
+            connects.push(tokio::time::Instant::now());
+            connects.len()
+        };
+        if attempt <= self.fail_first {
+            return Box::pin(async { Err(WsError::ConnectionClosed) });
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.session.lock().unwrap() = Some(tx);
+        let source = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|m| (Ok(m), rx))
+        });
+        let sink = futures_util::sink::unfold(self.sent.clone(), |sent, msg: Message| async move {
+            sent.lock().unwrap().push(msg);
+            Ok::<_, WsError>(sent)
+        });
+        Box::pin(async move { Ok((Box::pin(sink) as WsSink, Box::pin(source) as WsSource)) })
+    }
+}
@@ -0,0 +1,238 @@
+//! In-process stand-in for the Polymarket CLOB.
+//!
+//! Serves `/markets` from a fixture file, `/book` from the scripted books,
+//! accepts `/order`, `/orders` and the cancel endpoints, and exposes a market
+//! WebSocket that replays scripted `book` frames to every subscriber.
+
+use futures_util::{SinkExt, StreamExt};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A scripted `book` update for one token. Levels are `(price, size)`.
+#[derive(Debug, Clone)]
+pub struct BookFrame {
+    pub asset_id: String,
+    pub bids: Vec<(&'static str, &'static str)>,
+    pub asks: Vec<(&'static str, &'static str)>,
+}
+
+impl BookFrame {
+    pub fn new(asset_id: &str, bids: Vec<(&'static str, &'static str)>, asks: Vec<(&'static str, &'static str)>) -> Self {
+        Self { asset_id: asset_id.to_string(), bids, asks }
+    }
+
+    fn levels(levels: &[(&str, &str)]) -> Value {
+        Value::Array(levels.iter().map(|(p, s)| json!([p, s])).collect())
+    }
+
+    pub fn to_ws_json(&self) -> String {
+        json!({
+            "event_type": "book",
+            "asset_id": self.asset_id,
+            "bids": Self::levels(&self.bids),
+            "asks": Self::levels(&self.asks),
+            "hash": "",
+            "timestamp": chrono::Utc::now().timestamp_millis().to_string(),
+        }).to_string()
+    }
+}
+
+struct MockState {
+    markets: Value,
+    script: Vec<BookFrame>,
+    frame_delay: Duration,
+    books: Mutex<HashMap<String, BookFrame>>,
+    fill_ratios: Mutex<HashMap<String, Decimal>>, // token_id -> share of each order that matches
+    orders: Mutex<Vec<Value>>,
+    batches: Mutex<usize>,
+    cancels: Mutex<Vec<String>>,
+    requests: Mutex<Vec<String>>, // "METHOD /path", in arrival order
+}
+
+pub struct MockClob {
+    pub http_url: String,
+    pub ws_url: String,
+    state: Arc<MockState>,
+}
+
+impl MockClob {
+    /// Starts the HTTP and WebSocket servers on ephemeral local ports.
+    pub async fn start(markets_fixture: &Path, script: Vec<BookFrame>, frame_delay: Duration) -> Self {
+        let markets: Value = serde_json::from_str(&std::fs::read_to_string(markets_fixture).expect("read fixture"))
+            .expect("parse fixture");
+
+        let state = Arc::new(MockState {
+            markets,
+            script,
+            frame_delay,
+            books: Mutex::new(HashMap::new()),
+            fill_ratios: Mutex::new(HashMap::new()),
+            orders: Mutex::new(Vec::new()),
+            batches: Mutex::new(0),
+            cancels: Mutex::new(Vec::new()),
+            requests: Mutex::new(Vec::new()),
+        });
+
+        // HTTP
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let http_addr = listener.local_addr().unwrap();
+        let http_state = state.clone();
+        let make_svc = make_service_fn(move |_| {
+            let state = http_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| handle_http(state.clone(), req)))
+            }
+        });
+        let server = Server::from_tcp(listener).unwrap().serve(make_svc);
+        tokio::spawn(server);
+
+        // WebSocket
+        let ws_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_addr = ws_listener.local_addr().unwrap();
+        let ws_state = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = ws_listener.accept().await {
+                let state = ws_state.clone();
+                tokio::spawn(async move {
+                    if let Ok(ws) = tokio_tungstenite::accept_async(stream).await {
+                        replay(state, ws).await;
+                    }
+                });
+            }
+        });
+
+        Self {
+            http_url: format!("http://{}", http_addr),
+            ws_url: format!("ws://{}", ws_addr),
+            state,
+        }
+    }
+
+    /// Makes every order on `token_id` match only `ratio` of its size (0 = never fills).
+    pub fn set_fill_ratio(&self, token_id: &str, ratio: Decimal) {
+        self.state.fill_ratios.lock().unwrap().insert(token_id.to_string(), ratio);
+    }
+
+    /// Every order body received, whether posted alone or in a batch.
+    pub fn orders(&self) -> Vec<Value> {
+        self.state.orders.lock().unwrap().clone()
+    }
+
+    pub fn batch_count(&self) -> usize {
+        *self.state.batches.lock().unwrap()
+    }
+
+    /// Paths of the cancel endpoints hit, in order.
+    pub fn cancels(&self) -> Vec<String> {
+        self.state.cancels.lock().unwrap().clone()
+    }
+
+    /// Method and path of every HTTP request, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.requests.lock().unwrap().clone()
+    }
+}
+
+async fn replay(state: Arc<MockState>, ws: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>) {
+    let (mut write, mut read) = ws.split();
+
+    // Wait for the subscription before streaming
+    while let Some(Ok(msg)) = read.next().await {
+        if let Message::Text(_) = msg {
+            break;
+        }
+    }
+
+    for frame in &state.script {
+        tokio::time::sleep(state.frame_delay).await;
+        state.books.lock().unwrap().insert(frame.asset_id.clone(), frame.clone());
+        if write.send(Message::Text(frame.to_ws_json())).await.is_err() {
+            return;
+        }
+    }
+
+    // Keep the connection open until the client goes away
+    while let Some(Ok(_)) = read.next().await {}
+}
+
+async fn handle_http(state: Arc<MockState>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let query = req.uri().query().unwrap_or("").to_string();
+    state.requests.lock().unwrap().push(format!("{} {}", method, path));
+    let body = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let resp = match (method, path.as_str()) {
+        (Method::GET, "/markets") => json_response(state.markets.clone()),
+        (Method::GET, "/book") => {
+            let token_id = query.split('&').find_map(|kv| kv.strip_prefix("token_id=")).unwrap_or("");
+            match state.books.lock().unwrap().get(token_id) {
+                Some(book) => json_response(json!({
+                    "asset_id": book.asset_id,
+                    "bids": book.bids.iter().map(|(p, s)| json!({"price": p, "size": s})).collect::<Vec<_>>(),
+                    "asks": book.asks.iter().map(|(p, s)| json!({"price": p, "size": s})).collect::<Vec<_>>(),
+                })),
+                None => not_found(),
+            }
+        }
+        (Method::POST, "/order") => json_response(match_order(&state, &body)),
+        (Method::POST, "/orders") => {
+            *state.batches.lock().unwrap() += 1;
+            let legs = body.as_array().cloned().unwrap_or_default();
+            json_response(Value::Array(legs.iter().map(|o| match_order(&state, o)).collect()))
+        }
+        (Method::DELETE, _) => {
+            state.cancels.lock().unwrap().push(path.clone());
+            json_response(json!({ "canceled": [], "not_canceled": {} }))
+        }
+        _ => not_found(),
+    };
+    Ok(resp)
+}
+
+/// Fills `fill_ratio` of the order at its limit price.
+fn match_order(state: &MockState, order: &Value) -> Value {
+    state.orders.lock().unwrap().push(order.clone());
+
+    let token_id = order["token_id"].as_str().unwrap_or_default();
+    let price: Decimal = order["price"].as_str().and_then(|p| p.parse().ok()).unwrap_or_default();
+    let size: Decimal = order["size"].as_str().and_then(|s| s.parse().ok()).unwrap_or_default();
+    let ratio = state.fill_ratios.lock().unwrap().get(token_id).copied().unwrap_or(Decimal::ONE);
+
+    let filled = size * ratio;
+    if filled.is_zero() {
+        return json!({ "success": true, "errorMsg": "", "orderID": "", "status": "unmatched" });
+    }
+    let usdc = filled * price;
+    let (making, taking) = if order["side"] == "BUY" { (usdc, filled) } else { (filled, usdc) };
+    json!({
+        "success": true,
+        "errorMsg": "",
+        "orderID": "",
+        "status": "matched",
+        "makingAmount": making.to_string(),
+        "takingAmount": taking.to_string(),
+    })
+}
+
+fn json_response(body: Value) -> Response<Body> {
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn not_found() -> Response<Body> {
+    Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()).unwrap()
+}
@@ -0,0 +1,121 @@
+#![allow(dead_code)] // Each test binary uses a different subset of the helpers
+
+pub mod fakes;
+pub mod mock_clob;
+
+use chrono::{TimeZone, Utc};
+use mock_clob::BookFrame;
+use polymarket_arb_bot::capture::CaptureWriter;
+use polymarket_arb_bot::config::Config;
+use polymarket_arb_bot::safe_mode::SafeModeReason;
+use polymarket_arb_bot::types::{MarkPrice, MarketResponse, OrderType};
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Well-known development key (Hardhat/Anvil account #0). Never holds funds.
+pub const TEST_PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+pub fn market_fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("market_sample.json")
+}
+
+/// First market in the fixture that discovery accepts, as (condition_id, yes_token, no_token).
+pub fn fixture_crypto_market() -> (String, String, String) {
+    let text = std::fs::read_to_string(market_fixture()).unwrap();
+    let response: MarketResponse = serde_json::from_str(&text).unwrap();
+    let market = response.data.into_iter()
+        .find(|m| m.active && m.accepting_orders && m.tokens.len() == 2
+            && m.tags.as_ref().is_some_and(|t| t.iter().any(|t| t == "Crypto")))
+        .expect("fixture has a crypto market");
+    (market.condition_id, market.tokens[0].token_id.clone(), market.tokens[1].token_id.clone())
+}
+
+/// Defaults from `Config::from_env`, pointed at a mock exchange.
+pub fn test_config(http_url: &str, ws_url: &str) -> Config {
+    Config {
+        api_key: "test-key".to_string(),
+        api_secret: "dGVzdC1zZWNyZXQ=".to_string(),
+        api_passphrase: "test-pass".to_string(),
+        private_key: TEST_PRIVATE_KEY.to_string(),
+        funder_address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+        http_url: http_url.to_string(),
+        ws_url: ws_url.to_string(),
+        user_ws_url: ws_url.to_string(),
+        rpc_url: None,
+        max_daily_loss_pct: Decimal::new(2, 2),
+        max_trade_capital_pct: Decimal::new(1, 2),
+        min_edge: Decimal::new(5, 2),
+        max_market_capital_pct: Decimal::new(3, 2),
+        max_tag_capital_pct: Decimal::new(25, 2),
+        max_total_capital_pct: Decimal::new(5, 1),
+        max_open_positions: 20,
+        risk_day_offset: chrono::FixedOffset::east_opt(0).unwrap(),
+        risk_state_file: "risk_state.json".to_string(),
+        safe_mode_auto_recover: vec![
+            SafeModeReason::StaleData,
+            SafeModeReason::ReconciliationDrift,
+            SafeModeReason::OrderErrors,
+            SafeModeReason::OrderLatency,
+        ],
+        audit_log_file: "safe_mode_audit.jsonl".to_string(),
+        control_addr: None,
+        kill_switch_file: None,
+        kill_switch_flatten: false,
+        ws_disconnect_secs: 10,
+        breaker_window_secs: 300,
+        breaker_min_samples: 5,
+        max_order_error_rate: Decimal::new(5, 1),
+        max_order_latency_p99_ms: 2000,
+        max_partial_fills_per_hour: 3,
+        balance_sync_secs: 60,
+        balance_drift_tolerance: Decimal::ONE,
+        paper_balance: Decimal::from(1000),
+        mark_interval_secs: 5,
+        mark_price: MarkPrice::Bid,
+        poll_interval_ms: 250,
+        min_liquidity_multiplier: Decimal::new(5, 0),
+        normalization_threshold: Decimal::new(99, 2),
+        normalization_updates: 3,
+        trade_cooldown_ms: 30000,
+        arb_order_type: OrderType::Fok,
+        gtd_ttl_secs: 120,
+        batch_orders: true,
+        emergency_max_loss: Decimal::ONE,
+        emergency_min_sell_price: Decimal::new(1, 2),
+        emergency_max_steps: 5,
+        paper_trading: false,
+        paper_latency_ms: 0,
+        paper_slippage_bps: Decimal::ZERO,
+        paper_queue_share: Decimal::ONE,
+        capture_dir: None,
+        capture_rotate_secs: 3600,
+        replay_dir: None,
+        replay_speed: 0.0,
+    }
+}
+
+/// Polls `check` until it holds or `timeout` elapses.
+pub async fn wait_for(timeout: Duration, mut check: impl FnMut() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if check() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    check()
+}
+
+/// Writes the fixture markets and `frames`, `gap_ms` apart, to a fresh capture dir.
+pub fn write_capture(frames: &[BookFrame], gap_ms: i64) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("capture-test-{}", uuid::Uuid::new_v4()));
+    let start = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    let mut writer = CaptureWriter::new(&dir, 3600).unwrap();
+    writer.write_markets(start, &std::fs::read_to_string(market_fixture()).unwrap()).unwrap();
+    for (i, frame) in frames.iter().enumerate() {
+        let at = start + chrono::Duration::milliseconds(gap_ms * i as i64);
+        writer.write_frame(at, &frame.to_ws_json()).unwrap();
+    }
+    dir
+}
@@ -0,0 +1,78 @@
+//! Parameter sweeps over a synthetic capture.
+
+mod support;
+
+use polymarket_arb_bot::sweep::{self, SweepParam, SweepSpace};
+use rust_decimal::Decimal;
+use support::mock_clob::BookFrame;
+use support::{fixture_crypto_market, test_config, write_capture};
+
+#[test]
+fn ranks_grid_by_pnl() {
+    let (_, yes, no) = fixture_crypto_market();
+    // Two 0.10 dislocations, 8s of capture time apart
+    let mut frames = Vec::new();
+    for _ in 0..2 {
+        for _ in 0..3 {
+            frames.push(BookFrame::new(&yes, vec![("0.49", "100")], vec![("0.50", "100")]));
+            frames.push(BookFrame::new(&no, vec![("0.49", "100")], vec![("0.50", "100")]));
+        }
+        frames.push(BookFrame::new(&yes, vec![("0.39", "100")], vec![("0.40", "100")]));
+    }
+    let dir = write_capture(&frames, 1000);
+
+    let space = SweepSpace::new()
+        .axis(SweepParam::MinEdge, sweep::parse_values("0.05,0.15").unwrap())
+        .axis(SweepParam::TradeCooldownMs, sweep::parse_values("5000:30000:25000").unwrap());
+    let points = space.grid();
+    assert_eq!(points.len(), 4);
+
+    let results = sweep::run(&test_config("http://unused", "ws://unused"), &dir, Decimal::from(1000), &space, points, 4);
+    assert_eq!(results.len(), 4);
+
+    let best = &results[0];
+    assert_eq!(best.rank, 1);
+    assert_eq!(best.params["min_edge"], Decimal::new(5, 2));
+    assert_eq!(best.params["trade_cooldown_ms"], Decimal::from(5000));
+    assert_eq!((best.pnl, best.filled), (Decimal::from(2), 2));
+    assert_eq!((results[1].pnl, results[1].filled), (Decimal::ONE, 1));
+    // An edge threshold above the dislocation never trades
+    assert!(results[2..].iter().all(|r| r.attempts == 0 && r.params["min_edge"] == Decimal::new(15, 2)));
+
+    let csv = sweep::to_csv(&results);
+    assert!(csv.starts_with("rank,min_edge,trade_cooldown_ms,pnl,max_drawdown,"));
+    assert_eq!(csv.lines().count(), 5);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn samples_distinct_points_without_building_the_grid() {
+    let space = SweepSpace::new()
+        .axis(SweepParam::MinEdge, sweep::parse_values("0.01:0.10:0.01").unwrap())
+        .axis(SweepParam::NormalizationUpdates, sweep::parse_values("1:5:1").unwrap())
+        .axis(SweepParam::TradeCooldownMs, sweep::parse_values("0:9000:1000").unwrap());
+    let grid = space.grid();
+
+    let points = space.sample(40, 7);
+    assert_eq!(points.len(), 40);
+    assert!(points.iter().all(|p| grid.contains(p)));
+    let distinct: std::collections::HashSet<_> = points.iter().collect();
+    assert_eq!(distinct.len(), 40);
+    assert_eq!(space.sample(40, 7), points);
+    assert_ne!(space.sample(40, 8), points);
+
+    // Asking for more than there is returns the whole grid once
+    let mut all = space.sample(1000, 7);
+    all.sort();
+    assert_eq!(all, grid);
+
+    // 100^12 points would not fit in memory, let alone a usize
+    let values = sweep::parse_values("1:100:1").unwrap();
+    let huge = (0..12).fold(SweepSpace::new(), |space, _| space.axis(SweepParam::NormalizationUpdates, values.clone()));
+    let points = huge.sample(5, 1);
+    assert_eq!(points.len(), 5);
+    assert!(points.iter().all(|p| p.len() == 12));
+
+    assert!(SweepSpace::new().axis(SweepParam::MinEdge, vec![]).sample(5, 1).is_empty());
+}
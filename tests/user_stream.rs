@@ -0,0 +1,76 @@
+//! The authenticated user channel: subscribe, order events and trade statuses.
+
+mod support;
+
+use polymarket_arb_bot::clock::SystemClock;
+use polymarket_arb_bot::execution::ExecutionEngine;
+use polymarket_arb_bot::market::MarketMonitor;
+use polymarket_arb_bot::orders::OrderState;
+use polymarket_arb_bot::risk::RiskManager;
+use polymarket_arb_bot::safe_mode::SafeModeReason;
+use polymarket_arb_bot::transport::{HttpRequest, HttpResponse};
+use polymarket_arb_bot::types::{OrderRequest, OrderType, Side};
+use polymarket_arb_bot::user_stream::UserStream;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use support::fakes::{FakeHttp, FakeWs};
+use support::{test_config, wait_for};
+
+fn not_found(_: &HttpRequest) -> HttpResponse {
+    HttpResponse { status: 404, body: String::new() }
+}
+
+#[tokio::test]
+async fn failed_trade_halts_only_its_market() {
+    let config = test_config("http://fake", "ws://fake");
+    let market_ws = Arc::new(FakeWs::new(0));
+    let monitor = Arc::new(MarketMonitor::with_transport(config.clone(), Arc::new(SystemClock), Arc::new(FakeHttp::new(not_found)), market_ws));
+    let risk_manager = RiskManager::new(Decimal::from(1000), config.max_daily_loss_pct, config.max_trade_capital_pct);
+    let engine = Arc::new(ExecutionEngine::new(config.clone(), risk_manager.clone(), monitor));
+
+    let req = OrderRequest {
+        market_id: "m1".to_string(),
+        token_id: "yes-1".to_string(),
+        side: Side::Buy,
+        price: "0.40".parse().unwrap(),
+        size: Decimal::from(10),
+        order_type: OrderType::Gtc,
+        salt: 1,
+        nonce: 0,
+    };
+    engine.orders().create("0xorder", &req);
+    engine.orders().transition("0xorder", OrderState::Signed).unwrap();
+    engine.orders().transition("0xorder", OrderState::Submitted).unwrap();
+
+    let user_ws = Arc::new(FakeWs::new(0));
+    let stream = UserStream::with_connector(config.clone(), engine.clone(), risk_manager.clone(), user_ws.clone());
+    tokio::spawn(async move { stream.run_ws_loop().await });
+    assert!(wait_for(Duration::from_secs(5), || user_ws.is_connected()).await);
+
+    let subscribe: Value = serde_json::from_str(&user_ws.sent_text()[0]).unwrap();
+    assert_eq!(subscribe["type"], "user");
+    assert_eq!(subscribe["auth"]["apiKey"], config.api_key.as_str());
+
+    // Noise is skipped without dropping the session
+    user_ws.push("[]".to_string());
+    user_ws.push("not json".to_string());
+    user_ws.push(json!({ "event_type": "order", "id": "0xother", "market": "m1", "asset_id": "yes-1",
+        "type": "PLACEMENT", "price": "0.40", "original_size": "10", "size_matched": "0" }).to_string());
+
+    user_ws.push(json!([{ "event_type": "order", "id": "0xorder", "market": "m1", "asset_id": "yes-1",
+        "type": "PLACEMENT", "price": "0.40", "original_size": "10", "size_matched": "0" }]).to_string());
+    assert!(wait_for(Duration::from_secs(5), || engine.orders().get("0xorder").unwrap().state == OrderState::Live).await);
+
+    // Statuses are matched whatever their case
+    user_ws.push(json!([{ "event_type": "trade", "id": "t1", "market": "m1", "asset_id": "yes-1", "status": "failed",
+        "price": "0.40", "size": "10", "taker_order_id": "0xorder", "trader_side": "TAKER" }]).to_string());
+    assert!(wait_for(Duration::from_secs(5), || risk_manager.market_halt("m1").is_some()).await);
+
+    assert_eq!(risk_manager.market_halt("m1").unwrap().reason, SafeModeReason::SettlementFailed);
+    assert!(risk_manager.market_halt("m2").is_none());
+    assert!(!risk_manager.is_safe_mode());
+    assert_eq!(engine.orders().get("0xorder").unwrap().filled_size(), Decimal::ZERO);
+    assert!(user_ws.is_connected());
+}